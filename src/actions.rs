@@ -1,94 +1,31 @@
-use serde::{Deserialize, Deserializer};
-use serde_json::{Value, Map};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error as DeError;
+use serde_json::Value;
 use error::{WebDriverResult, WebDriverError, ErrorStatus};
-use common::WebElement;
+use common::{WebElement, WebReference};
+use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(PartialEq, Debug, Serialize)]
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
 pub struct ActionSequence {
     pub id: Option<String>,
+    #[serde(flatten)]
     pub actions: ActionsType
 }
 
-impl<'de> Deserialize<'de> for ActionSequence {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer<'de>
-    {
-        #[derive(Deserialize)]
-        #[serde(tag = "type", rename_all = "lowercase")]
-        enum Helper {
-            Null {
-                id: Option<String>,
-                actions: Vec<NullActionItem>,
-            },
-            Key {
-                id: Option<String>,
-                actions: Vec<KeyActionItem>,
-            },
-            Pointer {
-                id: Option<String>,
-                #[serde(default)]
-                parameters: PointerActionParameters,
-                actions: Vec<PointerActionItem>,
-            },
-        }
-
-        match Helper::deserialize(deserializer)? {
-            Helper::Null { id, actions } => {
-                Ok(ActionSequence {
-                    id: id,
-                    actions: ActionsType::Null{actions},
-                })
-            }
-            Helper::Key { id, actions } => {
-                Ok(ActionSequence {
-                    id: id,
-                    actions: ActionsType::Key{actions},
-                })
-            }
-            Helper::Pointer { id, parameters, actions } => {
-                Ok(ActionSequence {
-                    id: id,
-                    actions: ActionsType::Pointer{parameters, actions},
-                })
-            }
-        }
-    }
-}
-
-impl<'a> From<&'a ActionSequence> for Value {
-    fn from(params: &'a ActionSequence) -> Value {
-        let mut data: Map<String, Value> = Map::new();
-        data.insert("id".into(), params.id.clone().map(|x| x.into()).unwrap_or(Value::Null));
-        let (action_type, actions) = match params.actions {
-            ActionsType::Null {ref actions} => {
-                ("none",
-                 actions.iter().map(|x| x.into()).collect::<Vec<Value>>())
-            }
-            ActionsType::Key {ref actions} => {
-                ("key",
-                 actions.iter().map(|x| x.into()).collect::<Vec<Value>>())
-            }
-            ActionsType::Pointer {ref parameters, ref actions} => {
-                data.insert("parameters".into(), parameters.into());
-                ("pointer",
-                 actions.iter().map(|x| x.into()).collect::<Vec<Value>>())
-            }
-        };
-        data.insert("type".into(), action_type.into());
-        data.insert("actions".into(), actions.into());
-        Value::Object(data)
-    }
-}
-
-#[derive(PartialEq, Debug, Serialize)]
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum ActionsType {
+    #[serde(rename = "none")]
     Null {actions: Vec<NullActionItem>},
     Key {actions: Vec<KeyActionItem>},
-    Pointer {parameters: PointerActionParameters, actions:Vec<PointerActionItem>}
+    Pointer {
+        #[serde(default)]
+        parameters: PointerActionParameters,
+        actions: Vec<PointerActionItem>
+    },
+    Wheel {actions: Vec<WheelActionItem>}
 }
 
-
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(rename_all="lowercase")]
 pub enum PointerType {
@@ -97,16 +34,6 @@ pub enum PointerType {
     Touch,
 }
 
-impl<'a> From<&'a PointerType> for Value {
-    fn from(params: &'a PointerType) -> Value {
-        match *params {
-            PointerType::Mouse => "mouse".into(),
-            PointerType::Pen => "pen".into(),
-            PointerType::Touch => "touch".into(),
-        }
-    }
-}
-
 impl Default for PointerType {
     fn default() -> PointerType {
         PointerType::Mouse
@@ -119,29 +46,12 @@ pub struct PointerActionParameters {
     pub pointer_type: PointerType
 }
 
-impl<'a> From<&'a PointerActionParameters> for Value {
-    fn from(params: &'a PointerActionParameters) -> Value {
-        let mut data = Map::new();
-        data.insert("pointerType".to_owned(),
-                    (&params.pointer_type).into());
-        Value::Object(data)
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(untagged)]
 pub enum NullActionItem {
     General(GeneralAction)
 }
 
-impl<'a> From<&'a NullActionItem> for Value {
-    fn from(params: &'a NullActionItem) -> Value {
-        match *params {
-            NullActionItem::General(ref x) => x.into(),
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(untagged)]
 pub enum KeyActionItem {
@@ -149,15 +59,6 @@ pub enum KeyActionItem {
     Key(KeyAction)
 }
 
-impl<'a> From<&'a KeyActionItem> for Value {
-    fn from(params: &'a KeyActionItem) -> Value {
-        match *params {
-            KeyActionItem::General(ref x) => x.into(),
-            KeyActionItem::Key(ref x) => x.into()
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(untagged)]
 pub enum PointerActionItem {
@@ -165,45 +66,17 @@ pub enum PointerActionItem {
     Pointer(PointerAction)
 }
 
-impl<'a> From<&'a PointerActionItem> for Value {
-    fn from(params: &'a PointerActionItem) -> Value {
-        match *params {
-            PointerActionItem::General(ref x) => x.into(),
-            PointerActionItem::Pointer(ref x) => x.into()
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type")]
 pub enum GeneralAction {
     Pause(PauseAction)
 }
 
-impl<'a> From<&'a GeneralAction> for Value {
-    fn from(params: &'a GeneralAction) -> Value {
-        match *params {
-            GeneralAction::Pause(ref x) => x.into()
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct PauseAction {
     pub duration: u64
 }
 
-impl<'a> From<&'a PauseAction> for Value {
-    fn from(params: &'a PauseAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(),
-                    "pause".into());
-        data.insert("duration".to_owned(),
-                    params.duration.into());
-        Value::Object(data)
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type")]
 pub enum KeyAction {
@@ -213,78 +86,84 @@ pub enum KeyAction {
     Down(KeyDownAction)
 }
 
-impl<'a> From<&'a KeyAction> for Value {
-    fn from(params: &'a KeyAction) -> Value {
-        match *params {
-            KeyAction::Down(ref x) => x.into(),
-            KeyAction::Up(ref x) => x.into(),
-        }
-    }
-}
-
-fn validate_key_value(value_str: &str) -> WebDriverResult<char> {
-    let mut chars = value_str.chars();
-    let value = if let Some(c) = chars.next() {
+/// Validate that `value_str` is exactly one extended grapheme cluster, per
+/// the spec's definition of a key action value: a base scalar plus any
+/// trailing combining marks, a ZWJ-joined emoji sequence, or a paired
+/// regional-indicator flag all count as a single cluster.
+fn validate_key_value(value_str: &str) -> WebDriverResult<String> {
+    let mut clusters = value_str.graphemes(true);
+    let value = if let Some(c) = clusters.next() {
         c
     } else {
         return Err(WebDriverError::new(
             ErrorStatus::InvalidArgument,
             "Parameter 'value' was an empty string"))
     };
-    if chars.next().is_some() {
+    if clusters.next().is_some() {
         return Err(WebDriverError::new(
             ErrorStatus::InvalidArgument,
-            "Parameter 'value' contained multiple characters"))
+            "Parameter 'value' contained multiple grapheme clusters"))
     };
-    Ok(value)
+    Ok(value.to_string())
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub struct KeyUpAction {
-    pub value: char
+fn deserialize_key_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where D: Deserializer<'de>
+{
+    let value = String::deserialize(deserializer)?;
+    validate_key_value(&value).map_err(|e| DeError::custom(e.to_string()))
 }
 
-impl<'a> From<&'a KeyUpAction> for Value {
-    fn from(params: &'a KeyUpAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(),
-                    "keyUp".into());
-        data.insert("value".to_string(),
-                    params.value.to_string().into());
-        Value::Object(data)
-    }
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct KeyUpAction {
+    #[serde(deserialize_with = "deserialize_key_value")]
+    pub value: String
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct KeyDownAction {
-    pub value: char
-}
-
-impl<'a> From<&'a KeyDownAction> for Value {
-    fn from(params: &'a KeyDownAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(),
-                    "keyDown".into());
-        data.insert("value".to_owned(),
-                    params.value.to_string().into());
-        Value::Object(data)
-    }
+    #[serde(deserialize_with = "deserialize_key_value")]
+    pub value: String
 }
 
-#[derive(PartialEq, Serialize, Deserialize, Debug)]
-#[serde(untagged, rename_all="lowercase")]
+/// A pointer action's origin: the literal strings `"viewport"` or
+/// `"pointer"`, or an element/shadow root reference. Hand-rolled rather
+/// than `#[serde(untagged)]` because an untagged enum's unit variants
+/// serialize to (and only deserialize from) JSON `null`, not their variant
+/// name, which doesn't match the wire strings the spec actually uses.
+#[derive(PartialEq, Debug)]
 pub enum PointerOrigin {
     Viewport,
     Pointer,
-    Element(WebElement),
+    Element(WebReference),
+}
+
+impl Serialize for PointerOrigin {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *self {
+            PointerOrigin::Viewport => serializer.serialize_str("viewport"),
+            PointerOrigin::Pointer => serializer.serialize_str("pointer"),
+            PointerOrigin::Element(ref x) => x.serialize(serializer),
+        }
+    }
 }
 
-impl<'a> From<&'a PointerOrigin> for Value {
-    fn from(params: &'a PointerOrigin) -> Value {
-        match *params {
-            PointerOrigin::Viewport => "viewport".into(),
-            PointerOrigin::Pointer => "pointer".into(),
-            PointerOrigin::Element(ref x) => x.into(),
+impl<'de> Deserialize<'de> for PointerOrigin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let value = Value::deserialize(deserializer)?;
+        match value {
+            Value::String(ref x) if x == "viewport" => Ok(PointerOrigin::Viewport),
+            Value::String(ref x) if x == "pointer" => Ok(PointerOrigin::Pointer),
+            Value::Object(_) => {
+                WebReference::deserialize(value)
+                    .map(PointerOrigin::Element)
+                    .map_err(DeError::custom)
+            }
+            other => Err(DeError::custom(format!("invalid pointer origin: {}", other)))
         }
     }
 }
@@ -295,6 +174,50 @@ impl Default for PointerOrigin {
     }
 }
 
+/// A wheel action's origin: the literal string `"viewport"` or an
+/// element/shadow root reference. Unlike `PointerOrigin`, there is no
+/// `"pointer"` variant — a wheel action has no pointer of its own to anchor
+/// the scroll to.
+#[derive(PartialEq, Debug)]
+pub enum WheelOrigin {
+    Viewport,
+    Element(WebReference),
+}
+
+impl Serialize for WheelOrigin {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *self {
+            WheelOrigin::Viewport => serializer.serialize_str("viewport"),
+            WheelOrigin::Element(ref x) => x.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WheelOrigin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let value = Value::deserialize(deserializer)?;
+        match value {
+            Value::String(ref x) if x == "viewport" => Ok(WheelOrigin::Viewport),
+            Value::Object(_) => {
+                WebReference::deserialize(value)
+                    .map(WheelOrigin::Element)
+                    .map_err(DeError::custom)
+            }
+            other => Err(DeError::custom(format!("invalid wheel origin: {}", other)))
+        }
+    }
+}
+
+impl Default for WheelOrigin {
+    fn default() -> WheelOrigin {
+        WheelOrigin::Viewport
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type")]
 pub enum PointerAction {
@@ -308,79 +231,174 @@ pub enum PointerAction {
     Cancel
 }
 
-impl<'a> From<&'a PointerAction> for Value {
-    fn from(params: &'a PointerAction) -> Value {
-        match *params {
-            PointerAction::Down(ref x) => x.into(),
-            PointerAction::Up(ref x) => x.into(),
-            PointerAction::Move(ref x) => x.into(),
-            PointerAction::Cancel => {
-                let mut data = Map::new();
-                data.insert("type".to_owned(),
-                            "pointerCancel".into());
-                Value::Object(data)
-            }
+fn default_pointer_dimension() -> i64 { 1 }
+
+fn deserialize_pressure<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<f64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x < 0.0 || x > 1.0 {
+            return Err(DeError::custom("pressure must be between 0.0 and 1.0"));
         }
     }
+    Ok(value)
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub struct PointerUpAction {
-    pub button: u64,
+fn deserialize_tangential_pressure<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<f64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x < -1.0 || x > 1.0 {
+            return Err(DeError::custom("tangentialPressure must be between -1.0 and 1.0"));
+        }
+    }
+    Ok(value)
+}
+
+fn deserialize_tilt<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<i64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x < -90 || x > 90 {
+            return Err(DeError::custom("tilt must be between -90 and 90"));
+        }
+    }
+    Ok(value)
+}
+
+fn deserialize_twist<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<u64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x > 359 {
+            return Err(DeError::custom("twist must be between 0 and 359"));
+        }
+    }
+    Ok(value)
+}
+
+fn deserialize_altitude_angle<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<f64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x < 0.0 || x > ::std::f64::consts::FRAC_PI_2 {
+            return Err(DeError::custom("altitudeAngle must be between 0 and pi/2"));
+        }
+    }
+    Ok(value)
 }
 
-impl<'a> From<&'a PointerUpAction> for Value {
-    fn from(params: &'a PointerUpAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(),
-                    "pointerUp".into());
-        data.insert("button".to_owned(), params.button.into());
-        Value::Object(data)
+fn deserialize_azimuth_angle<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<f64> = Option::deserialize(deserializer)?;
+    if let Some(x) = value {
+        if x < 0.0 || x > 2.0 * ::std::f64::consts::PI {
+            return Err(DeError::custom("azimuthAngle must be between 0 and 2*pi"));
+        }
     }
+    Ok(value)
 }
 
+/// Pen/touch pointer properties shared by `PointerDownAction`,
+/// `PointerUpAction`, and `PointerMoveAction`, flattened into each so the
+/// wire representation keeps the properties alongside `button`/`x`/`y`.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub struct PointerDownAction {
+pub struct PointerCommonProperties {
+    #[serde(default = "default_pointer_dimension")]
+    pub width: i64,
+    #[serde(default = "default_pointer_dimension")]
+    pub height: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_pressure")]
+    pub pressure: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "tangentialPressure", deserialize_with = "deserialize_tangential_pressure")]
+    pub tangential_pressure: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "tiltX", deserialize_with = "deserialize_tilt")]
+    pub tilt_x: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "tiltY", deserialize_with = "deserialize_tilt")]
+    pub tilt_y: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_twist")]
+    pub twist: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "altitudeAngle", deserialize_with = "deserialize_altitude_angle")]
+    pub altitude_angle: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "azimuthAngle", deserialize_with = "deserialize_azimuth_angle")]
+    pub azimuth_angle: Option<f64>,
+}
+
+impl Default for PointerCommonProperties {
+    fn default() -> PointerCommonProperties {
+        PointerCommonProperties {
+            width: 1,
+            height: 1,
+            pressure: None,
+            tangential_pressure: None,
+            tilt_x: None,
+            tilt_y: None,
+            twist: None,
+            altitude_angle: None,
+            azimuth_angle: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct PointerUpAction {
     pub button: u64,
+    #[serde(flatten)]
+    pub common: PointerCommonProperties,
 }
 
-impl<'a> From<&'a PointerDownAction> for Value {
-    fn from(params: &'a PointerDownAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(),
-                    "pointerDown".into());
-        data.insert("button".to_owned(), params.button.into());
-        Value::Object(data)
-    }
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct PointerDownAction {
+    pub button: u64,
+    #[serde(flatten)]
+    pub common: PointerCommonProperties,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct PointerMoveAction {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<u64>,
     pub origin: PointerOrigin,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub x: Option<i64>,
-    pub y: Option<i64>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<i64>,
+    #[serde(flatten)]
+    pub common: PointerCommonProperties,
 }
 
-impl<'a> From<&'a PointerMoveAction> for Value {
-    fn from(params: &'a PointerMoveAction) -> Value {
-        let mut data = Map::new();
-        data.insert("type".to_owned(), "pointerMove".into());
-        if let Some(duration) = params.duration {
-            data.insert("duration".to_owned(),
-                        duration.into());
-        }
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum WheelActionItem {
+    General(GeneralAction),
+    Wheel(WheelAction)
+}
 
-        data.insert("origin".to_owned(), (&params.origin).into());
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "type")]
+pub enum WheelAction {
+    #[serde(rename="scroll")]
+    Scroll(WheelScrollAction)
+}
 
-        if let Some(x) = params.x {
-            data.insert("x".to_owned(), x.into());
-        }
-        if let Some(y) = params.y {
-            data.insert("y".to_owned(), y.into());
-        }
-        Value::Object(data)
-    }
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct WheelScrollAction {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+    #[serde(default)]
+    pub origin: WheelOrigin,
+    pub x: i64,
+    pub y: i64,
+    #[serde(rename="deltaX")]
+    pub delta_x: i64,
+    #[serde(rename="deltaY")]
+    pub delta_y: i64,
 }
 
 #[cfg(test)]
@@ -404,7 +422,8 @@ mod test {
                             PointerActionItem::Pointer (
                                 PointerAction::Down (
                                     PointerDownAction {
-                                        button: 0
+                                        button: 0,
+                                        common: PointerCommonProperties::default()
                                     }
                                 )
                             ),
@@ -414,7 +433,8 @@ mod test {
                                         duration: Some(100),
                                         x: Some(5),
                                         y: Some(10),
-                                        origin: PointerOrigin::Pointer
+                                        origin: PointerOrigin::Pointer,
+                                        common: PointerCommonProperties::default()
                                     }
                                 )
                             ),
@@ -425,17 +445,19 @@ mod test {
                                         x: Some(10),
                                         y: Some(20),
                                         origin: PointerOrigin::Element(
-                                            WebElement {
+                                            WebReference::Element(WebElement {
                                                 id: "elem".into()
-                                            }
-                                        )
+                                            })
+                                        ),
+                                        common: PointerCommonProperties::default()
                                     }
                                 )
                             ),
                             PointerActionItem::Pointer(
                                 PointerAction::Up (
                                     PointerUpAction {
-                                        button: 0
+                                        button: 0,
+                                        common: PointerCommonProperties::default()
                                     }
                                 )
                             ),
@@ -451,12 +473,153 @@ mod test {
 r#"{"actions": [
   {"type": "pointer", "actions": [
     {"type": "pointerDown", "button": 0},
-    {"type": "pointerMove", "x": 5, "y": 10, "origin": "relative"},
-    {"type": "pointerMove", "x": 5, "y": 10, "origin": {"element-6066-11e4-a52e-4f735466cecf": "elem"}},
+    {"type": "pointerMove", "duration": 100, "x": 5, "y": 10, "origin": "pointer"},
+    {"type": "pointerMove", "duration": 200, "x": 10, "y": 20, "origin": {"element-6066-11e4-a52e-4f735466cecf": "elem"}},
     {"type": "pointerUp", "button": 0},
     {"type": "pointerCancel"}
   ]
 }]}"#).unwrap();
         assert_eq!(actual, expected);
+
+        let value = serde_json::to_value(&expected).unwrap();
+        let round_tripped: ActionsParameters = serde_json::from_value(value).unwrap();
+        assert_eq!(round_tripped, expected);
+    }
+
+    #[test]
+    fn test_wheel() {
+        let expected = ActionsParameters {
+            actions: vec![
+                ActionSequence {
+                    id: None,
+                    actions: ActionsType::Wheel {
+                        actions: vec!{
+                            WheelActionItem::Wheel(
+                                WheelAction::Scroll(
+                                    WheelScrollAction {
+                                        duration: Some(100),
+                                        origin: WheelOrigin::Viewport,
+                                        x: 0,
+                                        y: 0,
+                                        delta_x: 0,
+                                        delta_y: 50
+                                    }
+                                )
+                            ),
+                        }
+                    }
+                }
+            ]
+        };
+        let actual: ActionsParameters = serde_json::from_str(
+r#"{"actions": [
+  {"type": "wheel", "actions": [
+    {"type": "scroll", "duration": 100, "origin": "viewport", "x": 0, "y": 0, "deltaX": 0, "deltaY": 50}
+  ]
+}]}"#).unwrap();
+        assert_eq!(actual, expected);
+
+        let value = serde_json::to_value(&expected).unwrap();
+        let round_tripped: ActionsParameters = serde_json::from_value(value).unwrap();
+        assert_eq!(round_tripped, expected);
+    }
+
+    #[test]
+    fn test_wheel_scroll_rejects_pointer_origin() {
+        let result: Result<WheelScrollAction, _> = serde_json::from_str(
+            r#"{"origin": "pointer", "x": 0, "y": 0, "deltaX": 0, "deltaY": 50}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_key_value_accepts_single_bmp_char() {
+        assert_eq!(validate_key_value("a").unwrap(), "a");
+    }
+
+    #[test]
+    fn test_validate_key_value_accepts_multi_codepoint_grapheme_cluster() {
+        // family emoji: four codepoints joined by ZWJ, one grapheme cluster.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        assert_eq!(validate_key_value(family).unwrap(), family);
+
+        // flag sequence: a pair of regional-indicator codepoints, one cluster.
+        let flag = "\u{1F1EB}\u{1F1F7}";
+        assert_eq!(validate_key_value(flag).unwrap(), flag);
+
+        // base letter plus a combining mark, one cluster.
+        let combining = "e\u{0301}";
+        assert_eq!(validate_key_value(combining).unwrap(), combining);
+    }
+
+    #[test]
+    fn test_validate_key_value_rejects_two_grapheme_clusters() {
+        assert!(validate_key_value("ab").is_err());
+    }
+
+    #[test]
+    fn test_validate_key_value_rejects_empty_string() {
+        assert!(validate_key_value("").is_err());
+    }
+
+    #[test]
+    fn test_pointer_common_properties_accepts_in_range_values() {
+        let parsed: PointerCommonProperties = serde_json::from_str(
+            r#"{"pressure": 0.5, "tangentialPressure": -0.5, "tiltX": -90, "tiltY": 90,
+                "twist": 359, "altitudeAngle": 0.0, "azimuthAngle": 0.0}"#).unwrap();
+        assert_eq!(parsed.pressure, Some(0.5));
+        assert_eq!(parsed.tangential_pressure, Some(-0.5));
+        assert_eq!(parsed.tilt_x, Some(-90));
+        assert_eq!(parsed.tilt_y, Some(90));
+        assert_eq!(parsed.twist, Some(359));
+        assert_eq!(parsed.altitude_angle, Some(0.0));
+        assert_eq!(parsed.azimuth_angle, Some(0.0));
+    }
+
+    #[test]
+    fn test_pointer_common_properties_rejects_out_of_range_values() {
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"pressure": -0.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"pressure": 1.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tangentialPressure": -1.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tangentialPressure": 1.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tiltX": -91}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tiltX": 91}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tiltY": -91}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"tiltY": 91}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"twist": 360}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"altitudeAngle": -0.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(
+            &format!(r#"{{"altitudeAngle": {}}}"#, ::std::f64::consts::FRAC_PI_2 + 0.1)).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(r#"{"azimuthAngle": -0.1}"#).is_err());
+        assert!(serde_json::from_str::<PointerCommonProperties>(
+            &format!(r#"{{"azimuthAngle": {}}}"#, 2.0 * ::std::f64::consts::PI + 0.1)).is_err());
+    }
+
+    #[test]
+    fn test_pointer_common_properties_round_trip() {
+        let minimal = PointerCommonProperties::default();
+        let value = serde_json::to_value(&minimal).unwrap();
+        let expected: Value = serde_json::from_str(r#"{"width": 1, "height": 1}"#).unwrap();
+        assert_eq!(value, expected);
+        let round_tripped: PointerCommonProperties = serde_json::from_value(value).unwrap();
+        assert_eq!(round_tripped, minimal);
+
+        let full = PointerCommonProperties {
+            width: 10,
+            height: 20,
+            pressure: Some(0.5),
+            tangential_pressure: Some(-0.2),
+            tilt_x: Some(45),
+            tilt_y: Some(-45),
+            twist: Some(180),
+            altitude_angle: Some(1.0),
+            azimuth_angle: Some(2.0),
+        };
+        let value = serde_json::to_value(&full).unwrap();
+        let expected: Value = serde_json::from_str(
+            r#"{"width": 10, "height": 20, "pressure": 0.5, "tangentialPressure": -0.2,
+                "tiltX": 45, "tiltY": -45, "twist": 180, "altitudeAngle": 1.0, "azimuthAngle": 2.0}"#).unwrap();
+        assert_eq!(value, expected);
+        let round_tripped: PointerCommonProperties = serde_json::from_value(value).unwrap();
+        assert_eq!(round_tripped, full);
     }
 }
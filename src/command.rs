@@ -1,10 +1,13 @@
 use actions::ActionSequence;
 use capabilities::{SpecNewSessionParametersWrapper, SpecNewSessionParameters, LegacyNewSessionParameters,
                    CapabilitiesMatching, BrowserCapabilities, Capabilities};
-use common::{Date, WebElement, FrameId, LocatorStrategy};
+use common::{Date, WebElement, ShadowRoot, FrameId, LocatorStrategy, CredentialParameters, MAX_SAFE_INTEGER};
 use error::{WebDriverResult, WebDriverError, ErrorStatus};
 use httpapi::{Route, WebDriverExtensionRoute, VoidWebDriverExtensionRoute};
+use hyper::Method;
 use regex::Captures;
+use serde::{Deserialize, Deserializer};
+use serde::de::Error as DeError;
 use serde_json::{self, Value, Map};
 use std::convert::From;
 
@@ -22,10 +25,12 @@ pub enum WebDriverCommand<T: WebDriverExtensionCommand> {
     GetWindowHandle,
     GetWindowHandles,
     CloseWindow,
+    NewWindow(NewWindowParameters),
     GetWindowRect,
     SetWindowRect(WindowRectParameters),
     MaximizeWindow,
-//    FullscreenWindow // Not supported in marionette
+    MinimizeWindow,
+    FullscreenWindow,
     SwitchToWindow(SwitchToWindowParameters),
     SwitchToFrame(SwitchToFrameParameters),
     SwitchToParentFrame,
@@ -33,6 +38,9 @@ pub enum WebDriverCommand<T: WebDriverExtensionCommand> {
     FindElements(LocatorParameters),
     FindElementElement(WebElement, LocatorParameters),
     FindElementElements(WebElement, LocatorParameters),
+    GetElementShadowRoot(WebElement),
+    FindShadowRootElement(ShadowRoot, LocatorParameters),
+    FindShadowRootElements(ShadowRoot, LocatorParameters),
     GetActiveElement,
     IsDisplayed(WebElement),
     IsSelected(WebElement),
@@ -64,12 +72,21 @@ pub enum WebDriverCommand<T: WebDriverExtensionCommand> {
     SendAlertText(SendKeysParameters),
     TakeScreenshot,
     TakeElementScreenshot(TakeScreenshotParameters),
+    Print(PrintParameters),
+    AddVirtualAuthenticator(AuthenticatorParameters),
+    RemoveVirtualAuthenticator(String),
+    AddCredential(String, CredentialParameters),
+    GetCredentials(String),
+    RemoveCredential(String, String),
+    RemoveAllCredentials(String),
+    SetUserVerified(String, UserVerifiedParameters),
     Status,
     Extension(T)
 }
 
 pub trait WebDriverExtensionCommand : Clone + Send + PartialEq {
     fn parameters_json(&self) -> Option<Value>;
+    fn endpoint(&self) -> (Method, String);
 }
 
 #[derive(Clone, PartialEq)]
@@ -79,6 +96,106 @@ impl WebDriverExtensionCommand for VoidWebDriverExtensionCommand {
     fn parameters_json(&self) -> Option<Value> {
         panic!("No extensions implemented");
     }
+
+    fn endpoint(&self) -> (Method, String) {
+        panic!("No extensions implemented");
+    }
+}
+
+impl <T: WebDriverExtensionCommand> WebDriverCommand<T> {
+    /// The HTTP method and path template a client should use to invoke this
+    /// command, mirroring the server-side `Route` table in `httpapi` but in
+    /// the client direction.
+    pub fn endpoint(&self, session_id: Option<&str>) -> (Method, String) {
+        let base = session_id.map(|id| format!("/session/{}", id)).unwrap_or_else(String::new);
+        match *self {
+            WebDriverCommand::NewSession(_) => (Method::Post, "/session".to_string()),
+            WebDriverCommand::DeleteSession => (Method::Delete, base),
+            WebDriverCommand::Get(_) => (Method::Post, format!("{}/url", base)),
+            WebDriverCommand::GetCurrentUrl => (Method::Get, format!("{}/url", base)),
+            WebDriverCommand::GoBack => (Method::Post, format!("{}/back", base)),
+            WebDriverCommand::GoForward => (Method::Post, format!("{}/forward", base)),
+            WebDriverCommand::Refresh => (Method::Post, format!("{}/refresh", base)),
+            WebDriverCommand::GetTitle => (Method::Get, format!("{}/title", base)),
+            WebDriverCommand::GetPageSource => (Method::Get, format!("{}/source", base)),
+            WebDriverCommand::GetWindowHandle => (Method::Get, format!("{}/window", base)),
+            WebDriverCommand::GetWindowHandles => (Method::Get, format!("{}/window/handles", base)),
+            WebDriverCommand::CloseWindow => (Method::Delete, format!("{}/window", base)),
+            WebDriverCommand::NewWindow(_) => (Method::Post, format!("{}/window/new", base)),
+            WebDriverCommand::GetWindowRect => (Method::Get, format!("{}/window/rect", base)),
+            WebDriverCommand::SetWindowRect(_) => (Method::Post, format!("{}/window/rect", base)),
+            WebDriverCommand::MaximizeWindow => (Method::Post, format!("{}/window/maximize", base)),
+            WebDriverCommand::MinimizeWindow => (Method::Post, format!("{}/window/minimize", base)),
+            WebDriverCommand::FullscreenWindow => (Method::Post, format!("{}/window/fullscreen", base)),
+            WebDriverCommand::SwitchToWindow(_) => (Method::Post, format!("{}/window", base)),
+            WebDriverCommand::SwitchToFrame(_) => (Method::Post, format!("{}/frame", base)),
+            WebDriverCommand::SwitchToParentFrame => (Method::Post, format!("{}/frame/parent", base)),
+            WebDriverCommand::FindElement(_) => (Method::Post, format!("{}/element", base)),
+            WebDriverCommand::FindElements(_) => (Method::Post, format!("{}/elements", base)),
+            WebDriverCommand::FindElementElement(ref e, _) =>
+                (Method::Post, format!("{}/element/{}/element", base, e.id)),
+            WebDriverCommand::FindElementElements(ref e, _) =>
+                (Method::Post, format!("{}/element/{}/elements", base, e.id)),
+            WebDriverCommand::GetElementShadowRoot(ref e) =>
+                (Method::Get, format!("{}/element/{}/shadow", base, e.id)),
+            WebDriverCommand::FindShadowRootElement(ref s, _) =>
+                (Method::Post, format!("{}/shadow/{}/element", base, s.id)),
+            WebDriverCommand::FindShadowRootElements(ref s, _) =>
+                (Method::Post, format!("{}/shadow/{}/elements", base, s.id)),
+            WebDriverCommand::GetActiveElement => (Method::Get, format!("{}/element/active", base)),
+            WebDriverCommand::IsDisplayed(ref e) => (Method::Get, format!("{}/element/{}/displayed", base, e.id)),
+            WebDriverCommand::IsSelected(ref e) => (Method::Get, format!("{}/element/{}/selected", base, e.id)),
+            WebDriverCommand::GetElementAttribute(ref e, ref name) =>
+                (Method::Get, format!("{}/element/{}/attribute/{}", base, e.id, name)),
+            WebDriverCommand::GetElementProperty(ref e, ref name) =>
+                (Method::Get, format!("{}/element/{}/property/{}", base, e.id, name)),
+            WebDriverCommand::GetCSSValue(ref e, ref name) =>
+                (Method::Get, format!("{}/element/{}/css/{}", base, e.id, name)),
+            WebDriverCommand::GetElementText(ref e) => (Method::Get, format!("{}/element/{}/text", base, e.id)),
+            WebDriverCommand::GetElementTagName(ref e) => (Method::Get, format!("{}/element/{}/name", base, e.id)),
+            WebDriverCommand::GetElementRect(ref e) => (Method::Get, format!("{}/element/{}/rect", base, e.id)),
+            WebDriverCommand::IsEnabled(ref e) => (Method::Get, format!("{}/element/{}/enabled", base, e.id)),
+            WebDriverCommand::ExecuteScript(_) => (Method::Post, format!("{}/execute/sync", base)),
+            WebDriverCommand::ExecuteAsyncScript(_) => (Method::Post, format!("{}/execute/async", base)),
+            WebDriverCommand::GetCookies => (Method::Get, format!("{}/cookie", base)),
+            WebDriverCommand::GetNamedCookie(ref name) => (Method::Get, format!("{}/cookie/{}", base, name)),
+            WebDriverCommand::AddCookie(_) => (Method::Post, format!("{}/cookie", base)),
+            WebDriverCommand::DeleteCookies => (Method::Delete, format!("{}/cookie", base)),
+            WebDriverCommand::DeleteCookie(ref name) => (Method::Delete, format!("{}/cookie/{}", base, name)),
+            WebDriverCommand::GetTimeouts => (Method::Get, format!("{}/timeouts", base)),
+            WebDriverCommand::SetTimeouts(_) => (Method::Post, format!("{}/timeouts", base)),
+            WebDriverCommand::ElementClick(ref e) => (Method::Post, format!("{}/element/{}/click", base, e.id)),
+            WebDriverCommand::ElementTap(ref e) => (Method::Post, format!("{}/element/{}/tap", base, e.id)),
+            WebDriverCommand::ElementClear(ref e) => (Method::Post, format!("{}/element/{}/clear", base, e.id)),
+            WebDriverCommand::ElementSendKeys(ref e, _) => (Method::Post, format!("{}/element/{}/value", base, e.id)),
+            WebDriverCommand::PerformActions(_) => (Method::Post, format!("{}/actions", base)),
+            WebDriverCommand::ReleaseActions => (Method::Delete, format!("{}/actions", base)),
+            WebDriverCommand::DismissAlert => (Method::Post, format!("{}/alert/dismiss", base)),
+            WebDriverCommand::AcceptAlert => (Method::Post, format!("{}/alert/accept", base)),
+            WebDriverCommand::GetAlertText => (Method::Get, format!("{}/alert/text", base)),
+            WebDriverCommand::SendAlertText(_) => (Method::Post, format!("{}/alert/text", base)),
+            WebDriverCommand::TakeScreenshot => (Method::Get, format!("{}/screenshot", base)),
+            WebDriverCommand::TakeElementScreenshot(ref p) =>
+                (Method::Get, format!("{}/element/{}/screenshot", base, p.element.id)),
+            WebDriverCommand::Print(_) => (Method::Post, format!("{}/print", base)),
+            WebDriverCommand::AddVirtualAuthenticator(_) =>
+                (Method::Post, format!("{}/webauthn/authenticator", base)),
+            WebDriverCommand::RemoveVirtualAuthenticator(ref id) =>
+                (Method::Delete, format!("{}/webauthn/authenticator/{}", base, id)),
+            WebDriverCommand::AddCredential(ref id, _) =>
+                (Method::Post, format!("{}/webauthn/authenticator/{}/credential", base, id)),
+            WebDriverCommand::GetCredentials(ref id) =>
+                (Method::Get, format!("{}/webauthn/authenticator/{}/credentials", base, id)),
+            WebDriverCommand::RemoveCredential(ref aid, ref cid) =>
+                (Method::Delete, format!("{}/webauthn/authenticator/{}/credentials/{}", base, aid, cid)),
+            WebDriverCommand::RemoveAllCredentials(ref id) =>
+                (Method::Delete, format!("{}/webauthn/authenticator/{}/credentials", base, id)),
+            WebDriverCommand::SetUserVerified(ref id, _) =>
+                (Method::Post, format!("{}/webauthn/authenticator/{}/uv", base, id)),
+            WebDriverCommand::Status => (Method::Get, "/status".to_string()),
+            WebDriverCommand::Extension(ref x) => x.endpoint(),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -123,6 +240,10 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
             Route::GetWindowHandle => WebDriverCommand::GetWindowHandle,
             Route::GetWindowHandles => WebDriverCommand::GetWindowHandles,
             Route::CloseWindow => WebDriverCommand::CloseWindow,
+            Route::NewWindow => {
+                let parameters: NewWindowParameters = serde_json::from_str(raw_body)?;
+                WebDriverCommand::NewWindow(parameters)
+            },
             Route::GetTimeouts => WebDriverCommand::GetTimeouts,
             Route::SetTimeouts => {
                 let parameters: TimeoutsParameters = serde_json::from_str(raw_body)?;
@@ -134,6 +255,8 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
                 WebDriverCommand::SetWindowRect(parameters)
             },
             Route::MaximizeWindow => WebDriverCommand::MaximizeWindow,
+            Route::MinimizeWindow => WebDriverCommand::MinimizeWindow,
+            Route::FullscreenWindow => WebDriverCommand::FullscreenWindow,
             Route::SwitchToWindow => {
                 let parameters: SwitchToWindowParameters = serde_json::from_str(raw_body)?;
                 WebDriverCommand::SwitchToWindow(parameters)
@@ -145,11 +268,23 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
             Route::SwitchToParentFrame => WebDriverCommand::SwitchToParentFrame,
             Route::FindElement => {
                 let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
-                WebDriverCommand::FindElement(parameters)
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, None, true);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindElement(parameters)
+                }
             },
             Route::FindElements => {
                 let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
-                WebDriverCommand::FindElements(parameters)
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, None, false);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindElements(parameters)
+                }
             },
             Route::FindElementElement => {
                 let element_id = try_opt!(params.name("elementId"),
@@ -157,7 +292,13 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
                                           "Missing elementId parameter");
                 let element = WebElement::new(element_id.as_str().into());
                 let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
-                WebDriverCommand::FindElementElement(element, parameters)
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, Some((&element).into()), true);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindElementElement(element, parameters)
+                }
             },
             Route::FindElementElements => {
                 let element_id = try_opt!(params.name("elementId"),
@@ -165,7 +306,48 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
                                           "Missing elementId parameter");
                 let element = WebElement::new(element_id.as_str().into());
                 let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
-                WebDriverCommand::FindElementElements(element, parameters)
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, Some((&element).into()), false);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindElementElements(element, parameters)
+                }
+            },
+            Route::GetElementShadowRoot => {
+                let element_id = try_opt!(params.name("elementId"),
+                                          ErrorStatus::InvalidArgument,
+                                          "Missing elementId parameter");
+                let element = WebElement::new(element_id.as_str().into());
+                WebDriverCommand::GetElementShadowRoot(element)
+            },
+            Route::FindShadowRootElement => {
+                let shadow_id = try_opt!(params.name("shadowId"),
+                                         ErrorStatus::InvalidArgument,
+                                         "Missing shadowId parameter");
+                let shadow_root = ShadowRoot::new(shadow_id.as_str().into());
+                let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, Some((&shadow_root).into()), true);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindShadowRootElement(shadow_root, parameters)
+                }
+            },
+            Route::FindShadowRootElements => {
+                let shadow_id = try_opt!(params.name("shadowId"),
+                                         ErrorStatus::InvalidArgument,
+                                         "Missing shadowId parameter");
+                let shadow_root = ShadowRoot::new(shadow_id.as_str().into());
+                let parameters: LocatorParameters = serde_json::from_str(raw_body)?;
+                if parameters.using == LocatorStrategy::Relative {
+                    let relative: RelativeLocatorParameters = serde_json::from_str(raw_body)?;
+                    let (script, args) = build_relative_locator_script(&relative, Some((&shadow_root).into()), false);
+                    WebDriverCommand::ExecuteScript(JavascriptCommandParameters { script: script, args: Some(args) })
+                } else {
+                    WebDriverCommand::FindShadowRootElements(shadow_root, parameters)
+                }
             },
             Route::GetActiveElement => WebDriverCommand::GetActiveElement,
             Route::IsDisplayed => {
@@ -324,6 +506,55 @@ impl<U: WebDriverExtensionRoute> WebDriverMessage<U> {
                 let parameters: TakeScreenshotParameters = serde_json::from_str(raw_body)?;
                 WebDriverCommand::TakeElementScreenshot(parameters)
             },
+            Route::Print => {
+                let parameters: PrintParameters = serde_json::from_str(raw_body)?;
+                WebDriverCommand::Print(parameters)
+            },
+            Route::AddVirtualAuthenticator => {
+                let parameters: AuthenticatorParameters = serde_json::from_str(raw_body)?;
+                WebDriverCommand::AddVirtualAuthenticator(parameters)
+            },
+            Route::RemoveVirtualAuthenticator => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                WebDriverCommand::RemoveVirtualAuthenticator(authenticator_id)
+            },
+            Route::AddCredential => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                let parameters: CredentialParameters = serde_json::from_str(raw_body)?;
+                WebDriverCommand::AddCredential(authenticator_id, parameters)
+            },
+            Route::GetCredentials => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                WebDriverCommand::GetCredentials(authenticator_id)
+            },
+            Route::RemoveCredential => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                let credential_id = try_opt!(params.name("credentialId"),
+                                             ErrorStatus::InvalidArgument,
+                                             "Missing credentialId parameter").as_str().into();
+                WebDriverCommand::RemoveCredential(authenticator_id, credential_id)
+            },
+            Route::RemoveAllCredentials => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                WebDriverCommand::RemoveAllCredentials(authenticator_id)
+            },
+            Route::SetUserVerified => {
+                let authenticator_id = try_opt!(params.name("authenticatorId"),
+                                                ErrorStatus::InvalidArgument,
+                                                "Missing authenticatorId parameter").as_str().into();
+                let parameters: UserVerifiedParameters = serde_json::from_str(raw_body)?;
+                WebDriverCommand::SetUserVerified(authenticator_id, parameters)
+            },
             Route::Status => WebDriverCommand::Status,
             Route::Extension(ref extension) => {
                 try!(extension.command(params, &body_data))
@@ -371,9 +602,14 @@ impl <U:WebDriverExtensionRoute> From<WebDriverMessage<U>> for Value {
             WebDriverCommand::DeleteCookies |
             WebDriverCommand::DeleteSession |
             WebDriverCommand::DismissAlert |
+            WebDriverCommand::GetCredentials(_) |
+            WebDriverCommand::RemoveAllCredentials(_) |
+            WebDriverCommand::RemoveCredential(_, _) |
+            WebDriverCommand::RemoveVirtualAuthenticator(_) |
             WebDriverCommand::ElementClear(_) |
             WebDriverCommand::ElementClick(_) |
             WebDriverCommand::ElementTap(_) |
+            WebDriverCommand::FullscreenWindow |
             WebDriverCommand::GetActiveElement |
             WebDriverCommand::GetAlertText |
             WebDriverCommand::GetNamedCookie(_) |
@@ -383,6 +619,7 @@ impl <U:WebDriverExtensionRoute> From<WebDriverMessage<U>> for Value {
             WebDriverCommand::GetElementAttribute(_, _) |
             WebDriverCommand::GetElementProperty(_, _) |
             WebDriverCommand::GetElementRect(_) |
+            WebDriverCommand::GetElementShadowRoot(_) |
             WebDriverCommand::GetElementTagName(_) |
             WebDriverCommand::GetElementText(_) |
             WebDriverCommand::GetPageSource |
@@ -397,6 +634,7 @@ impl <U:WebDriverExtensionRoute> From<WebDriverMessage<U>> for Value {
             WebDriverCommand::IsEnabled(_) |
             WebDriverCommand::IsSelected(_) |
             WebDriverCommand::MaximizeWindow |
+            WebDriverCommand::MinimizeWindow |
             WebDriverCommand::NewSession(_) |
             WebDriverCommand::Refresh |
             WebDriverCommand::Status |
@@ -407,17 +645,24 @@ impl <U:WebDriverExtensionRoute> From<WebDriverMessage<U>> for Value {
             },
 
             WebDriverCommand::AddCookie(ref x) => Some(x.into()),
+            WebDriverCommand::AddCredential(_, ref x) => Some(x.into()),
+            WebDriverCommand::AddVirtualAuthenticator(ref x) => Some(x.into()),
             WebDriverCommand::ElementSendKeys(_, ref x) => Some(x.into()),
-            WebDriverCommand::ExecuteAsyncScript(ref x) |
-            WebDriverCommand::ExecuteScript(ref x) => Some(x.into()),
+            WebDriverCommand::ExecuteScript(ref x) => Some(x.to_value(false)),
+            WebDriverCommand::ExecuteAsyncScript(ref x) => Some(x.to_value(true)),
             WebDriverCommand::FindElementElement(_, ref x) => Some(x.into()),
             WebDriverCommand::FindElementElements(_, ref x) => Some(x.into()),
             WebDriverCommand::FindElement(ref x) => Some(x.into()),
             WebDriverCommand::FindElements(ref x) => Some(x.into()),
+            WebDriverCommand::FindShadowRootElement(_, ref x) => Some(x.into()),
+            WebDriverCommand::FindShadowRootElements(_, ref x) => Some(x.into()),
             WebDriverCommand::Get(ref x) => Some(x.into()),
+            WebDriverCommand::NewWindow(ref x) => Some(x.into()),
             WebDriverCommand::PerformActions(ref x) => Some(x.into()),
+            WebDriverCommand::Print(ref x) => Some(x.into()),
             WebDriverCommand::SendAlertText(ref x) => Some(x.into()),
             WebDriverCommand::SetTimeouts(ref x) => Some(x.into()),
+            WebDriverCommand::SetUserVerified(_, ref x) => Some(x.into()),
             WebDriverCommand::SetWindowRect(ref x) => Some(x.into()),
             WebDriverCommand::SwitchToFrame(ref x) => Some(x.into()),
             WebDriverCommand::SwitchToWindow(ref x) => Some(x.into()),
@@ -495,11 +740,50 @@ impl<'a> From<&'a GetParameters> for Value {
     }
 }
 
+fn deserialize_safe_u64<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where D: Deserializer<'de>
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => {
+            let n = v.as_f64().ok_or_else(|| DeError::custom("expected a number"))?;
+            if n.fract() != 0.0 {
+                return Err(DeError::custom(format!("{} is not an integer", n)));
+            }
+            if n < 0.0 || n > MAX_SAFE_INTEGER as f64 {
+                return Err(DeError::custom(format!("{} is outside the safe integer range", n)));
+            }
+            Ok(Some(n as u64))
+        }
+    }
+}
+
+fn deserialize_safe_i64<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where D: Deserializer<'de>
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => {
+            let n = v.as_f64().ok_or_else(|| DeError::custom("expected a number"))?;
+            if n.fract() != 0.0 {
+                return Err(DeError::custom(format!("{} is not an integer", n)));
+            }
+            let max = MAX_SAFE_INTEGER as f64;
+            if n < -max || n > max {
+                return Err(DeError::custom(format!("{} is outside the safe integer range", n)));
+            }
+            Ok(Some(n as i64))
+        }
+    }
+}
+
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
 pub struct TimeoutsParameters {
+    #[serde(default, deserialize_with = "deserialize_safe_u64")]
     pub script: Option<u64>,
-    #[serde(rename="pageLoad")]
+    #[serde(rename="pageLoad", default, deserialize_with = "deserialize_safe_u64")]
     pub page_load: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_safe_u64")]
     pub implicit: Option<u64>,
 }
 
@@ -519,11 +803,31 @@ impl<'a> From<&'a TimeoutsParameters> for Value {
     }
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NewWindowParameters {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_hint: Option<String>,
+}
+
+impl<'a> From<&'a NewWindowParameters> for Value {
+    fn from(params: &'a NewWindowParameters) -> Value {
+        let mut data = Map::new();
+        if let Some(ref type_hint) = params.type_hint {
+            data.insert("type".to_string(), type_hint.clone().into());
+        }
+        Value::Object(data)
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowRectParameters {
+    #[serde(default, deserialize_with = "deserialize_safe_i64")]
     pub x: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_safe_i64")]
     pub y: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_safe_u64")]
     pub width: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_safe_u64")]
     pub height: Option<u64>,
 }
 
@@ -566,6 +870,202 @@ impl<'a> From<&'a LocatorParameters> for Value {
     }
 }
 
+fn default_near_threshold() -> u64 { 50 }
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum RelativeLocatorKind {
+    Above,
+    Below,
+    ToLeftOf,
+    ToRightOf,
+    Near
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RelativeLocatorAnchor {
+    Element(WebElement),
+    Locator(LocatorParameters)
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct RelativeLocatorRelation {
+    pub kind: RelativeLocatorKind,
+    #[serde(default = "default_near_threshold")]
+    pub threshold: u64,
+    pub anchor: RelativeLocatorAnchor
+}
+
+fn default_relative_locator_base_strategy() -> LocatorStrategy { LocatorStrategy::CSSSelector }
+
+/// Parameters for a Selenium-4-style relative locator: a base locator
+/// narrowed down by a list of spatial relations to anchor elements.
+/// WebDriver has no native "relative" strategy, so these are realised by
+/// generating a DOM-query script (see `build_relative_locator_script`)
+/// rather than dispatched directly. A request whose top-level `using` is
+/// `Relative` is intercepted in `from_http` and turned into an
+/// `ExecuteScript` command before it ever reaches a
+/// `WebDriverCommand::FindElement`/`FindElements` variant.
+///
+/// The base locator's strategy is carried in `base_using`, *not* `using`:
+/// `from_http` re-parses the very same request body that was already
+/// deserialized once to read the top-level `using: "relative"` dispatch
+/// discriminant, so a field also named `using` here would always come back
+/// `Relative` too, making every base locator indistinguishable from the
+/// wrapper around it. Defaults to `CSSSelector`, the common case, when
+/// omitted.
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct RelativeLocatorParameters {
+    #[serde(default = "default_relative_locator_base_strategy", rename = "baseUsing")]
+    pub base_using: LocatorStrategy,
+    pub value: String,
+    pub relations: Vec<RelativeLocatorRelation>
+}
+
+/// Build a script expression yielding an array of the elements matched by
+/// `locator` within `scope` (a script expression evaluating to a `Document`
+/// or `Element`), dispatching on `locator.using` the same way the rest of
+/// the driver does.
+fn locator_candidates_script(locator: &LocatorParameters, scope: &str) -> String {
+    let value = serde_json::to_string(&locator.value).unwrap();
+    match locator.using {
+        LocatorStrategy::CSSSelector =>
+            format!("Array.prototype.slice.call({}.querySelectorAll({}))", scope, value),
+        LocatorStrategy::XPath => format!(
+            "(function() {{\n\
+             var result = document.evaluate({value}, {scope}, null,\n\
+             XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);\n\
+             var nodes = [];\n\
+             for (var i = 0; i < result.snapshotLength; i++) {{\n\
+             nodes.push(result.snapshotItem(i));\n\
+             }}\n\
+             return nodes;\n\
+             }})()",
+            value = value, scope = scope),
+        LocatorStrategy::LinkText | LocatorStrategy::PartialLinkText => format!(
+            "Array.prototype.slice.call({scope}.getElementsByTagName(\"a\")).filter(function(el) {{\n\
+             return {comparison};\n\
+             }})",
+            scope = scope,
+            comparison = if locator.using == LocatorStrategy::PartialLinkText {
+                format!("el.textContent.indexOf({}) !== -1", value)
+            } else {
+                format!("el.textContent === {}", value)
+            }),
+        LocatorStrategy::Relative =>
+            "(function() { throw new Error('relative locators cannot anchor another relative locator'); })()".to_string()
+    }
+}
+
+/// Build a script expression for the anchor element of `relation`: either
+/// the sole match of a nested locator (scoped to the whole document), or a
+/// `WebElement` resolved back to its live DOM node via the `arguments`
+/// array, the same mechanism `JavascriptCommandParameters` uses for any
+/// other element reference passed into a script.
+fn relative_locator_anchor_script(anchor: &RelativeLocatorAnchor, args: &mut Vec<Value>) -> String {
+    match *anchor {
+        RelativeLocatorAnchor::Element(ref element) => {
+            args.push(element.into());
+            format!("arguments[{}]", args.len() - 1)
+        },
+        RelativeLocatorAnchor::Locator(ref locator) =>
+            format!("({})[0]", locator_candidates_script(locator, "document"))
+    }
+}
+
+fn relative_locator_kind_filter(kind: &RelativeLocatorKind, threshold: u64) -> String {
+    match *kind {
+        RelativeLocatorKind::Above =>
+            "rect.bottom <= anchorRect.top && rect.left < anchorRect.right && rect.right > anchorRect.left".to_string(),
+        RelativeLocatorKind::Below =>
+            "rect.top >= anchorRect.bottom && rect.left < anchorRect.right && rect.right > anchorRect.left".to_string(),
+        RelativeLocatorKind::ToLeftOf =>
+            "rect.right <= anchorRect.left && rect.top < anchorRect.bottom && rect.bottom > anchorRect.top".to_string(),
+        RelativeLocatorKind::ToRightOf =>
+            "rect.left >= anchorRect.right && rect.top < anchorRect.bottom && rect.bottom > anchorRect.top".to_string(),
+        RelativeLocatorKind::Near =>
+            format!("distance(rect, anchorRect) <= {}", threshold)
+    }
+}
+
+/// Build the body of a script implementing `parameters`, for dispatch via
+/// `WebDriverCommand::ExecuteScript`. Candidates matching the base locator
+/// are narrowed by each relation in turn and the survivors are returned
+/// ordered by proximity to the last anchor's centre. `root`, if given, is
+/// pushed onto the returned args and used in place of `document` as the
+/// scope the base locator searches within (for the `*Element`/
+/// `*ShadowRootElement` routes); `first_only` selects between returning the
+/// closest single match (`FindElement`) or all of them (`FindElements`).
+///
+/// The returned string is a bare function body, not a self-invoking
+/// expression — it is wrapped by `wrap_script` like any other
+/// `JavascriptCommandParameters.script`, so it must not wrap itself or its
+/// `return` would be swallowed by the outer IIFE instead of reaching the
+/// caller.
+pub fn build_relative_locator_script(parameters: &RelativeLocatorParameters,
+                                      root: Option<Value>,
+                                      first_only: bool) -> (String, Vec<Value>) {
+    let mut args = Vec::new();
+    let scope = match root {
+        Some(value) => {
+            args.push(value);
+            format!("arguments[{}]", args.len() - 1)
+        },
+        None => "document".to_string()
+    };
+
+    let mut filters = Vec::new();
+    for relation in &parameters.relations {
+        let anchor_script = relative_locator_anchor_script(&relation.anchor, &mut args);
+        let filter = relative_locator_kind_filter(&relation.kind, relation.threshold);
+        filters.push(format!(
+            "{{\n\
+             var anchorRect = ({}).getBoundingClientRect();\n\
+             candidates = candidates.filter(function(el) {{\n\
+             var rect = el.getBoundingClientRect();\n\
+             return {};\n\
+             }});\n\
+             lastAnchorRect = anchorRect;\n\
+             }}",
+            anchor_script, filter));
+    }
+
+    let base_locator = LocatorParameters { using: parameters.base_using, value: parameters.value.clone() };
+    let result = if first_only {
+        "candidates.length ? candidates[0] : null"
+    } else {
+        "candidates"
+    };
+
+    let script = format!(
+        "function distance(a, b) {{\n\
+         var dx = Math.max(a.left - b.right, b.left - a.right, 0);\n\
+         var dy = Math.max(a.top - b.bottom, b.top - a.bottom, 0);\n\
+         return Math.sqrt(dx * dx + dy * dy);\n\
+         }}\n\
+         function centerDistance(a, b) {{\n\
+         var dx = (a.left + a.right) / 2 - (b.left + b.right) / 2;\n\
+         var dy = (a.top + a.bottom) / 2 - (b.top + b.bottom) / 2;\n\
+         return Math.sqrt(dx * dx + dy * dy);\n\
+         }}\n\
+         var candidates = {candidates};\n\
+         var lastAnchorRect = null;\n\
+         {filters}\n\
+         if (lastAnchorRect) {{\n\
+         candidates.sort(function(a, b) {{\n\
+         return centerDistance(a.getBoundingClientRect(), lastAnchorRect) -\n\
+         centerDistance(b.getBoundingClientRect(), lastAnchorRect);\n\
+         }});\n\
+         }}\n\
+         return {result};",
+        candidates = locator_candidates_script(&base_locator, &scope),
+        filters = filters.join("\n"),
+        result = result);
+
+    (script, args)
+}
+
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
 pub struct SwitchToFrameParameters {
     pub id: Option<FrameId>
@@ -595,27 +1095,69 @@ impl<'a> From<&'a SendKeysParameters> for Value {
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
 pub struct JavascriptCommandParameters {
     pub script: String,
+    #[serde(default)]
     pub args: Option<Vec<Value>>
 }
 
-impl<'a> From<&'a JavascriptCommandParameters> for Value {
-    fn from(params: &'a JavascriptCommandParameters) -> Value {
+impl JavascriptCommandParameters {
+    /// Build the marionette-ready request body for this script. `is_async`
+    /// selects between the `execute/sync` and `execute/async` wrapping
+    /// performed by `wrap_script`.
+    fn to_value(&self, is_async: bool) -> Value {
+        let mut args = self.args.clone().unwrap_or_else(Vec::new);
+        let script = wrap_script(&self.script, is_async, &mut args);
+
         let mut data = Map::new();
-        //TODO: Wrap script so that it becomes marionette-compatible
-        data.insert("script".to_string(), params.script.clone().into());
-        data.insert("args".to_string(), params.args.clone()
-                    .map(|x| Value::Array(x))
-                    .unwrap_or(Value::Null));
+        data.insert("script".to_string(), script.into());
+        data.insert("args".to_string(), Value::Array(args));
         Value::Object(data)
     }
 }
 
+/// Wrap a user-supplied script body in the IIFE marionette expects. A
+/// synchronous script runs immediately and returns its value directly; an
+/// asynchronous script runs inside a `Promise` that resolves via a
+/// completion callback appended as the trailing entry of `args`, and
+/// rejects if the body throws.
+pub fn wrap_script(script: &str, is_async: bool, args: &mut Vec<Value>) -> String {
+    if is_async {
+        args.push(Value::Null);
+        format!(
+            "(function() {{\n\
+             var outerArgs = arguments;\n\
+             return new Promise(function(resolve, reject) {{\n\
+             var args = Array.prototype.slice.call(outerArgs, 0, -1);\n\
+             args.push(function(value) {{ resolve(value); }});\n\
+             try {{\n\
+             (function() {{\n{}\n}}).apply(null, args);\n\
+             }} catch (e) {{\n\
+             reject(e);\n\
+             }}\n\
+             }});\n\
+             }}).apply(null, arguments)",
+            script)
+    } else {
+        format!(
+            "(function() {{\n\
+             return (function() {{\n{}\n}}).apply(null, arguments);\n\
+             }}).apply(null, arguments)",
+            script)
+    }
+}
+
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
 pub struct AddCookieParametersWrapper {
     cookie: AddCookieParameters
 }
 
-#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None
+}
+
+#[derive(PartialEq, Serialize, Debug)]
 pub struct AddCookieParameters {
     pub name: String,
     pub value: String,
@@ -623,7 +1165,45 @@ pub struct AddCookieParameters {
     pub domain: Option<String>,
     pub expiry: Option<Date>,
     pub secure: bool,
-    pub httpOnly: bool
+    pub httpOnly: bool,
+    #[serde(rename = "sameSite")]
+    pub same_site: Option<SameSite>
+}
+
+impl<'de> Deserialize<'de> for AddCookieParameters {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            name: String,
+            value: String,
+            path: Option<String>,
+            domain: Option<String>,
+            expiry: Option<Date>,
+            secure: bool,
+            httpOnly: bool,
+            #[serde(default, rename = "sameSite")]
+            same_site: Option<SameSite>
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        if helper.same_site == Some(SameSite::None) && !helper.secure {
+            return Err(DeError::custom(
+                "cookie with sameSite=None must also have secure=true"));
+        }
+
+        Ok(AddCookieParameters {
+            name: helper.name,
+            value: helper.value,
+            path: helper.path,
+            domain: helper.domain,
+            expiry: helper.expiry,
+            secure: helper.secure,
+            httpOnly: helper.httpOnly,
+            same_site: helper.same_site
+        })
+    }
 }
 
 impl<'a> From<&'a AddCookieParameters> for Value {
@@ -636,19 +1216,167 @@ impl<'a> From<&'a AddCookieParameters> for Value {
         data.insert("expiry".to_string(), params.expiry.clone().map(|x| x.into()).unwrap_or(Value::Null));
         data.insert("secure".to_string(), params.secure.into());
         data.insert("httpOnly".to_string(), params.httpOnly.into());
+        if let Some(same_site) = params.same_site {
+            let value = match same_site {
+                SameSite::Strict => "Strict",
+                SameSite::Lax => "Lax",
+                SameSite::None => "None"
+            };
+            data.insert("sameSite".to_string(), value.into());
+        }
         Value::Object(data)
     }
 }
 
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
 pub struct TakeScreenshotParameters {
-    pub element: Option<WebElement>
+    pub element: WebElement
 }
 
 impl<'a> From<&'a TakeScreenshotParameters> for Value {
     fn from(params: &'a TakeScreenshotParameters) -> Value {
         let mut data = Map::new();
-        data.insert("element".to_string(), params.element.clone().map(|x| (&x).into()).unwrap_or(Value::Null));
+        data.insert("element".to_string(), (&params.element).into());
+        Value::Object(data)
+    }
+}
+
+fn deserialize_print_scale<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where D: Deserializer<'de>
+{
+    let value = f64::deserialize(deserializer)?;
+    if value < 0.1 || value > 2.0 {
+        return Err(DeError::custom(format!("scale {} is outside the range 0.1-2.0", value)));
+    }
+    Ok(value)
+}
+
+fn deserialize_non_negative<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where D: Deserializer<'de>
+{
+    let value = f64::deserialize(deserializer)?;
+    if value < 0.0 {
+        return Err(DeError::custom(format!("dimension {} must not be negative", value)));
+    }
+    Ok(value)
+}
+
+fn default_print_scale() -> f64 { 1.0 }
+fn default_print_page_width() -> f64 { 21.59 }
+fn default_print_page_height() -> f64 { 27.94 }
+fn default_print_margin() -> f64 { 1.0 }
+fn default_print_shrink_to_fit() -> bool { true }
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PrintOrientation {
+    Portrait,
+    Landscape,
+}
+
+impl Default for PrintOrientation {
+    fn default() -> PrintOrientation {
+        PrintOrientation::Portrait
+    }
+}
+
+impl<'a> From<&'a PrintOrientation> for Value {
+    fn from(params: &'a PrintOrientation) -> Value {
+        match *params {
+            PrintOrientation::Portrait => "portrait".into(),
+            PrintOrientation::Landscape => "landscape".into(),
+        }
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct PrintPageParameters {
+    #[serde(default = "default_print_page_width", deserialize_with = "deserialize_non_negative")]
+    pub width: f64,
+    #[serde(default = "default_print_page_height", deserialize_with = "deserialize_non_negative")]
+    pub height: f64,
+}
+
+impl Default for PrintPageParameters {
+    fn default() -> PrintPageParameters {
+        PrintPageParameters {
+            width: default_print_page_width(),
+            height: default_print_page_height(),
+        }
+    }
+}
+
+impl<'a> From<&'a PrintPageParameters> for Value {
+    fn from(params: &'a PrintPageParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("width".to_string(), params.width.into());
+        data.insert("height".to_string(), params.height.into());
+        Value::Object(data)
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct PrintMarginParameters {
+    #[serde(default = "default_print_margin", deserialize_with = "deserialize_non_negative")]
+    pub top: f64,
+    #[serde(default = "default_print_margin", deserialize_with = "deserialize_non_negative")]
+    pub bottom: f64,
+    #[serde(default = "default_print_margin", deserialize_with = "deserialize_non_negative")]
+    pub left: f64,
+    #[serde(default = "default_print_margin", deserialize_with = "deserialize_non_negative")]
+    pub right: f64,
+}
+
+impl Default for PrintMarginParameters {
+    fn default() -> PrintMarginParameters {
+        PrintMarginParameters {
+            top: default_print_margin(),
+            bottom: default_print_margin(),
+            left: default_print_margin(),
+            right: default_print_margin(),
+        }
+    }
+}
+
+impl<'a> From<&'a PrintMarginParameters> for Value {
+    fn from(params: &'a PrintMarginParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("top".to_string(), params.top.into());
+        data.insert("bottom".to_string(), params.bottom.into());
+        data.insert("left".to_string(), params.left.into());
+        data.insert("right".to_string(), params.right.into());
+        Value::Object(data)
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct PrintParameters {
+    #[serde(default)]
+    pub orientation: PrintOrientation,
+    #[serde(default = "default_print_scale", deserialize_with = "deserialize_print_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub background: bool,
+    #[serde(default = "default_print_shrink_to_fit", rename = "shrinkToFit")]
+    pub shrink_to_fit: bool,
+    #[serde(default, rename = "pageRanges")]
+    pub page_ranges: Vec<Value>,
+    #[serde(default)]
+    pub page: PrintPageParameters,
+    #[serde(default)]
+    pub margin: PrintMarginParameters,
+}
+
+impl<'a> From<&'a PrintParameters> for Value {
+    fn from(params: &'a PrintParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("orientation".to_string(), (&params.orientation).into());
+        data.insert("scale".to_string(), params.scale.into());
+        data.insert("background".to_string(), params.background.into());
+        data.insert("shrinkToFit".to_string(), params.shrink_to_fit.into());
+        data.insert("pageRanges".to_string(), params.page_ranges.clone().into());
+        data.insert("page".to_string(), (&params.page).into());
+        data.insert("margin".to_string(), (&params.margin).into());
         Value::Object(data)
     }
 }
@@ -662,7 +1390,415 @@ impl<'a> From<&'a ActionsParameters> for Value {
     fn from(params: &'a ActionsParameters) -> Value {
         let mut data = Map::new();
         data.insert("actions".to_owned(),
-                    params.actions.iter().map(|x| x.into()).collect::<Vec<Value>>().into());
+                    params.actions.iter()
+                        .map(|x| serde_json::to_value(x).unwrap())
+                        .collect::<Vec<Value>>().into());
+        Value::Object(data)
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct AuthenticatorParameters {
+    pub protocol: String,
+    pub transport: String,
+    #[serde(rename="hasResidentKey")]
+    pub has_resident_key: bool,
+    #[serde(rename="hasUserVerification")]
+    pub has_user_verification: bool,
+    #[serde(rename="isUserConsenting")]
+    pub is_user_consenting: bool,
+    #[serde(rename="isUserVerified")]
+    pub is_user_verified: bool,
+}
+
+impl<'a> From<&'a AuthenticatorParameters> for Value {
+    fn from(params: &'a AuthenticatorParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("protocol".to_string(), params.protocol.clone().into());
+        data.insert("transport".to_string(), params.transport.clone().into());
+        data.insert("hasResidentKey".to_string(), params.has_resident_key.into());
+        data.insert("hasUserVerification".to_string(), params.has_user_verification.into());
+        data.insert("isUserConsenting".to_string(), params.is_user_consenting.into());
+        data.insert("isUserVerified".to_string(), params.is_user_verified.into());
+        Value::Object(data)
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug)]
+pub struct UserVerifiedParameters {
+    #[serde(rename="isUserVerified")]
+    pub is_user_verified: bool,
+}
+
+impl<'a> From<&'a UserVerifiedParameters> for Value {
+    fn from(params: &'a UserVerifiedParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("isUserVerified".to_string(), params.is_user_verified.into());
         Value::Object(data)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cookie_with_same_site(same_site: Option<SameSite>, secure: bool) -> AddCookieParameters {
+        AddCookieParameters {
+            name: "a".to_string(),
+            value: "b".to_string(),
+            path: None,
+            domain: None,
+            expiry: None,
+            secure: secure,
+            httpOnly: false,
+            same_site: same_site
+        }
+    }
+
+    #[test]
+    fn test_same_site_none_requires_secure() {
+        let body = r#"{"name":"a","value":"b","path":null,"domain":null,"expiry":null,
+                       "secure":false,"httpOnly":false,"sameSite":"None"}"#;
+        let result: Result<AddCookieParameters, _> = serde_json::from_str(body);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_same_site_none_with_secure_is_accepted() {
+        let body = r#"{"name":"a","value":"b","path":null,"domain":null,"expiry":null,
+                       "secure":true,"httpOnly":false,"sameSite":"None"}"#;
+        let parameters: AddCookieParameters = serde_json::from_str(body).unwrap();
+        assert_eq!(parameters.same_site, Some(SameSite::None));
+    }
+
+    #[test]
+    fn test_same_site_round_trips_through_serde_json() {
+        let cookie = cookie_with_same_site(Some(SameSite::Lax), true);
+        let serialized = serde_json::to_string(&cookie).unwrap();
+        assert!(serialized.contains("\"sameSite\":\"Lax\""));
+        let parsed: AddCookieParameters = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(parsed.same_site, Some(SameSite::Lax));
+    }
+
+    #[test]
+    fn test_timeouts_rejects_value_outside_safe_integer_range() {
+        let body = format!("{{\"script\":{}}}", MAX_SAFE_INTEGER as f64 + 1.0);
+        let result: Result<TimeoutsParameters, _> = serde_json::from_str(&body);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_timeouts_accepts_value_within_safe_integer_range() {
+        let body = format!("{{\"script\":{}}}", MAX_SAFE_INTEGER);
+        let parameters: TimeoutsParameters = serde_json::from_str(&body).unwrap();
+        assert_eq!(parameters.script, Some(MAX_SAFE_INTEGER as u64));
+    }
+
+    fn locator(using: LocatorStrategy, value: &str) -> LocatorParameters {
+        LocatorParameters { using, value: value.to_string() }
+    }
+
+    #[test]
+    fn test_locator_candidates_script_css_selector() {
+        let script = locator_candidates_script(&locator(LocatorStrategy::CSSSelector, ".foo"), "document");
+        assert_eq!(script, "Array.prototype.slice.call(document.querySelectorAll(\".foo\"))");
+    }
+
+    #[test]
+    fn test_locator_candidates_script_xpath() {
+        let script = locator_candidates_script(&locator(LocatorStrategy::XPath, "//div"), "document");
+        assert!(script.contains("document.evaluate(\"//div\", document, null,"));
+        assert!(script.contains("XPathResult.ORDERED_NODE_SNAPSHOT_TYPE"));
+    }
+
+    #[test]
+    fn test_locator_candidates_script_link_text() {
+        let script = locator_candidates_script(&locator(LocatorStrategy::LinkText, "Home"), "document");
+        assert!(script.contains("document.getElementsByTagName(\"a\")"));
+        assert!(script.contains("el.textContent === \"Home\""));
+    }
+
+    #[test]
+    fn test_locator_candidates_script_partial_link_text() {
+        let script = locator_candidates_script(&locator(LocatorStrategy::PartialLinkText, "Hom"), "document");
+        assert!(script.contains("el.textContent.indexOf(\"Hom\") !== -1"));
+    }
+
+    #[test]
+    fn test_locator_candidates_script_relative_anchor_throws() {
+        let script = locator_candidates_script(&locator(LocatorStrategy::Relative, "ignored"), "document");
+        assert!(script.contains("throw new Error"));
+    }
+
+    #[test]
+    fn test_relative_locator_kind_filter_above() {
+        let filter = relative_locator_kind_filter(&RelativeLocatorKind::Above, 50);
+        assert_eq!(filter,
+                   "rect.bottom <= anchorRect.top && rect.left < anchorRect.right && rect.right > anchorRect.left");
+    }
+
+    #[test]
+    fn test_relative_locator_kind_filter_below() {
+        let filter = relative_locator_kind_filter(&RelativeLocatorKind::Below, 50);
+        assert_eq!(filter,
+                   "rect.top >= anchorRect.bottom && rect.left < anchorRect.right && rect.right > anchorRect.left");
+    }
+
+    #[test]
+    fn test_relative_locator_kind_filter_to_left_of() {
+        let filter = relative_locator_kind_filter(&RelativeLocatorKind::ToLeftOf, 50);
+        assert_eq!(filter,
+                   "rect.right <= anchorRect.left && rect.top < anchorRect.bottom && rect.bottom > anchorRect.top");
+    }
+
+    #[test]
+    fn test_relative_locator_kind_filter_to_right_of() {
+        let filter = relative_locator_kind_filter(&RelativeLocatorKind::ToRightOf, 50);
+        assert_eq!(filter,
+                   "rect.left >= anchorRect.right && rect.top < anchorRect.bottom && rect.bottom > anchorRect.top");
+    }
+
+    #[test]
+    fn test_relative_locator_kind_filter_near() {
+        let filter = relative_locator_kind_filter(&RelativeLocatorKind::Near, 25);
+        assert_eq!(filter, "distance(rect, anchorRect) <= 25");
+    }
+
+    #[test]
+    fn test_relative_locator_anchor_script_element_uses_arguments() {
+        let mut args = Vec::new();
+        let anchor = RelativeLocatorAnchor::Element(WebElement::new("abc123".to_string()));
+        let script = relative_locator_anchor_script(&anchor, &mut args);
+        assert_eq!(script, "arguments[0]");
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0], Value::from(&WebElement::new("abc123".to_string())));
+    }
+
+    #[test]
+    fn test_relative_locator_anchor_script_locator_takes_first_match() {
+        let mut args = Vec::new();
+        let anchor = RelativeLocatorAnchor::Locator(locator(LocatorStrategy::CSSSelector, "#anchor"));
+        let script = relative_locator_anchor_script(&anchor, &mut args);
+        assert!(script.ends_with("[0]"));
+        assert!(script.contains("document.querySelectorAll(\"#anchor\")"));
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_build_relative_locator_script_pushes_root_as_first_argument() {
+        let parameters = RelativeLocatorParameters {
+            base_using: LocatorStrategy::CSSSelector,
+            value: ".foo".to_string(),
+            relations: Vec::new()
+        };
+        let root: Value = (&WebElement::new("root-id".to_string())).into();
+        let (script, args) = build_relative_locator_script(&parameters, Some(root.clone()), true);
+        assert_eq!(args, vec![root]);
+        assert!(script.contains("arguments[0].querySelectorAll(\".foo\")"));
+    }
+
+    #[test]
+    fn test_build_relative_locator_script_skips_sort_with_no_relations() {
+        let parameters = RelativeLocatorParameters {
+            base_using: LocatorStrategy::CSSSelector,
+            value: ".foo".to_string(),
+            relations: Vec::new()
+        };
+        let (script, _) = build_relative_locator_script(&parameters, None, false);
+        assert!(script.contains("if (lastAnchorRect)"));
+    }
+
+    #[test]
+    fn test_build_relative_locator_script_first_only_returns_closest_or_null() {
+        let parameters = RelativeLocatorParameters {
+            base_using: LocatorStrategy::CSSSelector,
+            value: ".foo".to_string(),
+            relations: vec![RelativeLocatorRelation {
+                kind: RelativeLocatorKind::Near,
+                threshold: 50,
+                anchor: RelativeLocatorAnchor::Element(WebElement::new("anchor-id".to_string()))
+            }]
+        };
+        let (script, args) = build_relative_locator_script(&parameters, None, true);
+        assert!(script.contains("candidates.length ? candidates[0] : null"));
+        assert_eq!(args.len(), 1);
+    }
+
+    #[test]
+    fn test_from_http_find_element_relative_queries_base_locator() {
+        use regex::Regex;
+
+        let params = Regex::new("(?P<sessionId>.*)").unwrap().captures("s1").unwrap();
+        let message: WebDriverMessage<VoidWebDriverExtensionRoute> = WebDriverMessage::from_http(
+            Route::FindElement,
+            &params,
+            r#"{"using":"relative","value":".foo","relations":[]}"#,
+            true).unwrap();
+
+        match message.command {
+            WebDriverCommand::ExecuteScript(ref parameters) => {
+                assert!(parameters.script.contains("document.querySelectorAll(\".foo\")"));
+                assert!(!parameters.script.contains("cannot anchor another relative locator"));
+            },
+            _ => panic!("expected WebDriverCommand::ExecuteScript"),
+        }
+    }
+
+    #[test]
+    fn test_wrap_script_sync_applies_arguments_directly() {
+        let mut args = vec![Value::from(1), Value::from(2)];
+        let script = wrap_script("return arguments[0] + arguments[1];", false, &mut args);
+        assert_eq!(args, vec![Value::from(1), Value::from(2)]);
+        assert!(script.contains("return (function() {\nreturn arguments[0] + arguments[1];\n}).apply(null, arguments);"));
+    }
+
+    #[test]
+    fn test_wrap_script_async_captures_outer_arguments_before_promise() {
+        let mut args = vec![Value::from(1), Value::from(2)];
+        let script = wrap_script("resolve_cb(arguments[0]);", true, &mut args);
+
+        // A trailing null placeholder is appended to reserve the completion
+        // callback's slot; the wrapped script drops it and passes its own
+        // resolve function there. The original args must be left untouched
+        // ahead of it.
+        assert_eq!(args, vec![Value::from(1), Value::from(2), Value::Null]);
+
+        // `outerArgs` must be captured from the IIFE's own `arguments` before
+        // the `Promise` executor is entered, since the executor's `arguments`
+        // is always `(resolve, reject)` and would otherwise shadow the real
+        // caller-supplied values.
+        let outer_capture = script.find("var outerArgs = arguments;").expect("captures outer arguments");
+        let promise_start = script.find("new Promise").expect("wraps body in a Promise");
+        assert!(outer_capture < promise_start);
+        assert!(script.contains("Array.prototype.slice.call(outerArgs, 0, -1)"));
+    }
+
+    #[test]
+    fn test_new_window_parameters_value_omits_absent_type_hint() {
+        let parameters = NewWindowParameters { type_hint: None };
+        let value: Value = (&parameters).into();
+        assert_eq!(value, Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn test_new_window_parameters_value_includes_present_type_hint() {
+        let parameters = NewWindowParameters { type_hint: Some("tab".to_string()) };
+        let value: Value = (&parameters).into();
+        let mut expected = Map::new();
+        expected.insert("type".to_string(), "tab".into());
+        assert_eq!(value, Value::Object(expected));
+    }
+
+    fn void_command(command: WebDriverCommand<VoidWebDriverExtensionCommand>)
+        -> WebDriverCommand<VoidWebDriverExtensionCommand>
+    {
+        command
+    }
+
+    #[test]
+    fn test_endpoint_no_args() {
+        let (method, path) = void_command(WebDriverCommand::Status).endpoint(None);
+        assert_eq!((method, path), (Method::Get, "/status".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_session_id_only() {
+        let (method, path) = void_command(WebDriverCommand::GetCurrentUrl).endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Get, "/session/abc/url".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_session_element_id() {
+        let command = void_command(WebDriverCommand::GetElementText(WebElement::new("e1".into())));
+        let (method, path) = command.endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Get, "/session/abc/element/e1/text".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_session_alert() {
+        let (method, path) = void_command(WebDriverCommand::GetAlertText).endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Get, "/session/abc/alert/text".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_shadow_root() {
+        let locator = locator(LocatorStrategy::CSSSelector, ".foo");
+        let command = void_command(
+            WebDriverCommand::FindShadowRootElement(ShadowRoot::new("s1".into()), locator));
+        let (method, path) = command.endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Post, "/session/abc/shadow/s1/element".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_webauthn() {
+        let params = AuthenticatorParameters {
+            protocol: "ctap2".to_string(),
+            transport: "usb".to_string(),
+            has_resident_key: false,
+            has_user_verification: false,
+            is_user_consenting: true,
+            is_user_verified: true,
+        };
+        let command = void_command(WebDriverCommand::AddVirtualAuthenticator(params));
+        let (method, path) = command.endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Post, "/session/abc/webauthn/authenticator".to_string()));
+    }
+
+    #[test]
+    fn test_endpoint_print() {
+        let params = PrintParameters {
+            orientation: PrintOrientation::Portrait,
+            scale: 1.0,
+            background: false,
+            shrink_to_fit: true,
+            page_ranges: Vec::new(),
+            page: PrintPageParameters::default(),
+            margin: PrintMarginParameters::default(),
+        };
+        let command = void_command(WebDriverCommand::Print(params));
+        let (method, path) = command.endpoint(Some("abc"));
+        assert_eq!((method, path), (Method::Post, "/session/abc/print".to_string()));
+    }
+
+    #[test]
+    fn test_print_defaults() {
+        let parameters: PrintParameters = serde_json::from_str("{}").unwrap();
+        assert_eq!(parameters.orientation, PrintOrientation::Portrait);
+        assert_eq!(parameters.scale, 1.0);
+        assert_eq!(parameters.shrink_to_fit, true);
+        assert_eq!(parameters.page, PrintPageParameters { width: 21.59, height: 27.94 });
+        assert_eq!(parameters.margin, PrintMarginParameters { top: 1.0, bottom: 1.0, left: 1.0, right: 1.0 });
+    }
+
+    #[test]
+    fn test_print_scale_rejects_below_minimum() {
+        let result: Result<PrintParameters, _> = serde_json::from_str(r#"{"scale": 0.09}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_print_scale_rejects_above_maximum() {
+        let result: Result<PrintParameters, _> = serde_json::from_str(r#"{"scale": 2.01}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_print_scale_accepts_bounds() {
+        let low: PrintParameters = serde_json::from_str(r#"{"scale": 0.1}"#).unwrap();
+        assert_eq!(low.scale, 0.1);
+        let high: PrintParameters = serde_json::from_str(r#"{"scale": 2.0}"#).unwrap();
+        assert_eq!(high.scale, 2.0);
+    }
+
+    #[test]
+    fn test_print_page_rejects_negative_dimension() {
+        let result: Result<PrintParameters, _> = serde_json::from_str(r#"{"page": {"width": -1.0}}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_print_margin_rejects_negative_dimension() {
+        let result: Result<PrintParameters, _> = serde_json::from_str(r#"{"margin": {"top": -1.0}}"#);
+        assert!(result.is_err());
+    }
+}
@@ -1,9 +1,17 @@
+use base64;
+use serde::{Deserialize, Deserializer};
+use serde::de::Error as SerdeError;
 use serde_json::{Value, Map};
 use std::convert::From;
 
 use error::{WebDriverResult, WebDriverError, ErrorStatus};
 
 pub static ELEMENT_KEY: &'static str = "element-6066-11e4-a52e-4f735466cecf";
+pub static SHADOW_KEY: &'static str = "shadow-6066-11e4-a52e-4f735466cecf";
+
+/// The largest integer the spec allows across the wire: 2^53 - 1, the
+/// largest value a JavaScript number can represent exactly.
+pub const MAX_SAFE_INTEGER: i64 = 9007199254740991;
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct Date(pub u64);
@@ -69,7 +77,7 @@ impl <T> From<T> for WebElement
 #[serde(untagged)]
 pub enum FrameId {
     Short(u16),
-    Element(WebElement)
+    Element(WebReference)
 }
 
 impl From<FrameId> for Value {
@@ -79,12 +87,151 @@ impl From<FrameId> for Value {
                 Value::Number(x.into())
             },
             FrameId::Element(ref x) => {
-                Value::String(x.id.clone())
+                Value::String(x.id().to_string())
             }
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShadowRoot {
+    #[serde(rename="shadow-6066-11e4-a52e-4f735466cecf")]
+    pub id: String
+}
+
+impl ShadowRoot {
+    pub fn new(id: String) -> ShadowRoot {
+        ShadowRoot {
+            id: id
+        }
+    }
+
+    pub fn from_json(data: &Value) -> WebDriverResult<ShadowRoot> {
+        let object = try_opt!(data.as_object(),
+                              ErrorStatus::InvalidArgument,
+                              "Could not convert shadow root to object");
+        let id_value = try_opt!(object.get(SHADOW_KEY),
+                                ErrorStatus::InvalidArgument,
+                                "Could not find shadow root key");
+
+        let id = try_opt!(id_value.as_str(),
+                          ErrorStatus::InvalidArgument,
+                          "Could not convert shadow root to string").to_string();
+
+        Ok(ShadowRoot::new(id))
+    }
+}
+
+impl <'a> From<&'a ShadowRoot> for Value {
+    fn from(shadow_root: &'a ShadowRoot) -> Value {
+        let mut data = Map::new();
+        data.insert(SHADOW_KEY.to_string(), shadow_root.id.clone().into());
+        Value::Object(data)
+    }
+}
+
+impl <T> From<T> for ShadowRoot
+    where T: Into<String> {
+    fn from(data: T) -> ShadowRoot {
+        ShadowRoot::new(data.into())
+    }
+}
+
+/// A web reference that may point to either an element or a shadow root,
+/// distinguished by which of `ELEMENT_KEY`/`SHADOW_KEY` is present on the
+/// wire. Lets commands that can operate on either kind of node (e.g. frame
+/// switching, pointer action origins) round-trip shadow-rooted nodes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WebReference {
+    Element(WebElement),
+    ShadowRoot(ShadowRoot),
+}
+
+impl WebReference {
+    pub fn from_json(data: &Value) -> WebDriverResult<WebReference> {
+        let object = try_opt!(data.as_object(),
+                              ErrorStatus::InvalidArgument,
+                              "Could not convert web reference to object");
+        if object.contains_key(ELEMENT_KEY) {
+            Ok(WebReference::Element(WebElement::from_json(data)?))
+        } else if object.contains_key(SHADOW_KEY) {
+            Ok(WebReference::ShadowRoot(ShadowRoot::from_json(data)?))
+        } else {
+            Err(WebDriverError::new(ErrorStatus::InvalidArgument,
+                                    "Could not find element or shadow root key"))
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match *self {
+            WebReference::Element(ref x) => &x.id,
+            WebReference::ShadowRoot(ref x) => &x.id,
+        }
+    }
+}
+
+impl<'a> From<&'a WebReference> for Value {
+    fn from(reference: &'a WebReference) -> Value {
+        match *reference {
+            WebReference::Element(ref x) => x.into(),
+            WebReference::ShadowRoot(ref x) => x.into(),
+        }
+    }
+}
+
+fn deserialize_base64url<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where D: Deserializer<'de>
+{
+    let value = String::deserialize(deserializer)?;
+    base64::decode_config(&value, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| SerdeError::custom(format!("value is not valid base64url: {}", e)))?;
+    Ok(value)
+}
+
+fn deserialize_base64url_opt<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    if let Some(ref s) = value {
+        base64::decode_config(s, base64::URL_SAFE_NO_PAD)
+            .map_err(|e| SerdeError::custom(format!("value is not valid base64url: {}", e)))?;
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CredentialParameters {
+    #[serde(rename="credentialId", deserialize_with="deserialize_base64url")]
+    pub credential_id: String,
+    #[serde(rename="isResidentCredential")]
+    pub is_resident_key: bool,
+    #[serde(rename="rpId")]
+    pub rp_id: Option<String>,
+    #[serde(rename="privateKey", deserialize_with="deserialize_base64url")]
+    pub private_key: String,
+    #[serde(rename="userHandle", default, deserialize_with="deserialize_base64url_opt")]
+    pub user_handle: Option<String>,
+    #[serde(rename="signCount")]
+    pub signature_count: u64,
+    #[serde(rename="largeBlob", default, deserialize_with="deserialize_base64url_opt")]
+    pub large_blob: Option<String>,
+}
+
+impl <'a> From<&'a CredentialParameters> for Value {
+    fn from(params: &'a CredentialParameters) -> Value {
+        let mut data = Map::new();
+        data.insert("credentialId".to_string(), params.credential_id.clone().into());
+        data.insert("isResidentCredential".to_string(), params.is_resident_key.into());
+        data.insert("rpId".to_string(), params.rp_id.clone().map(|x| x.into()).unwrap_or(Value::Null));
+        data.insert("privateKey".to_string(), params.private_key.clone().into());
+        data.insert("userHandle".to_string(), params.user_handle.clone().map(|x| x.into()).unwrap_or(Value::Null));
+        data.insert("signCount".to_string(), params.signature_count.into());
+        data.insert("largeBlob".to_string(), params.large_blob.clone().map(|x| x.into()).unwrap_or(Value::Null));
+        Value::Object(data)
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub enum LocatorStrategy {
     #[serde(rename = "css selector")]
@@ -94,7 +241,9 @@ pub enum LocatorStrategy {
     #[serde(rename = "partial link text")]
     PartialLinkText,
     #[serde(rename = "xpath")]
-    XPath
+    XPath,
+    #[serde(rename = "relative")]
+    Relative
 }
 
 impl LocatorStrategy {
@@ -106,6 +255,7 @@ impl LocatorStrategy {
             "link text" => Ok(LocatorStrategy::LinkText),
             "partial link text" => Ok(LocatorStrategy::PartialLinkText),
             "xpath" => Ok(LocatorStrategy::XPath),
+            "relative" => Ok(LocatorStrategy::Relative),
             x => Err(WebDriverError::new(ErrorStatus::InvalidArgument,
                                          format!("Unknown locator strategy {}", x)))
         }
@@ -118,7 +268,76 @@ impl From<LocatorStrategy> for Value {
             LocatorStrategy::CSSSelector => "css selector",
             LocatorStrategy::LinkText => "link text",
             LocatorStrategy::PartialLinkText => "partial link text",
-            LocatorStrategy::XPath => "xpath"
+            LocatorStrategy::XPath => "xpath",
+            LocatorStrategy::Relative => "relative"
         }.into()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_web_element_round_trips_through_json() {
+        let element = WebElement::new("abc123".to_string());
+        let value: Value = (&element).into();
+        let expected: Value = serde_json::from_str(
+            r#"{"element-6066-11e4-a52e-4f735466cecf": "abc123"}"#).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(WebElement::from_json(&value).unwrap(), element);
+    }
+
+    #[test]
+    fn test_shadow_root_round_trips_through_json() {
+        let shadow_root = ShadowRoot::new("abc123".to_string());
+        let value: Value = (&shadow_root).into();
+        let expected: Value = serde_json::from_str(
+            r#"{"shadow-6066-11e4-a52e-4f735466cecf": "abc123"}"#).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(ShadowRoot::from_json(&value).unwrap(), shadow_root);
+    }
+
+    #[test]
+    fn test_web_reference_from_json_distinguishes_element_and_shadow_root() {
+        let element: Value = serde_json::from_str(
+            r#"{"element-6066-11e4-a52e-4f735466cecf": "abc123"}"#).unwrap();
+        assert_eq!(WebReference::from_json(&element).unwrap(),
+                   WebReference::Element(WebElement::new("abc123".to_string())));
+
+        let shadow_root: Value = serde_json::from_str(
+            r#"{"shadow-6066-11e4-a52e-4f735466cecf": "def456"}"#).unwrap();
+        assert_eq!(WebReference::from_json(&shadow_root).unwrap(),
+                   WebReference::ShadowRoot(ShadowRoot::new("def456".to_string())));
+    }
+
+    #[test]
+    fn test_web_reference_from_json_rejects_unrelated_object() {
+        let value: Value = serde_json::from_str(r#"{"not-a-reference": "abc123"}"#).unwrap();
+        assert!(WebReference::from_json(&value).is_err());
+    }
+
+    #[test]
+    fn test_locator_strategy_round_trips_through_json() {
+        for strategy in &[LocatorStrategy::CSSSelector, LocatorStrategy::LinkText,
+                          LocatorStrategy::PartialLinkText, LocatorStrategy::XPath,
+                          LocatorStrategy::Relative] {
+            let value: Value = (*strategy).into();
+            assert_eq!(LocatorStrategy::from_json(&value).unwrap(), *strategy);
+        }
+    }
+
+    #[test]
+    fn test_credential_parameters_rejects_invalid_base64url() {
+        let body = r#"{
+            "credentialId": "not valid base64url!",
+            "isResidentCredential": true,
+            "rpId": null,
+            "privateKey": "AAAA",
+            "signCount": 0
+        }"#;
+        let result: Result<CredentialParameters, _> = serde_json::from_str(body);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use command::AddCookieParameters;
+use common::Date;
+
+/// Parse cookies out of the classic Netscape `cookie.txt` format (as
+/// produced by curl's `--cookie-jar` and many browsers). Each cookie is a
+/// tab-separated line of `domain`, `include_subdomains`, `path`, `secure`,
+/// `expiry`, `name`, `value`; `#`-prefixed lines are comments, except for
+/// an `#HttpOnly_` domain prefix which marks the cookie as HttpOnly.
+/// Malformed lines are skipped.
+pub fn from_netscape(input: &str) -> Vec<AddCookieParameters> {
+    let mut cookies = Vec::new();
+
+    for raw_line in input.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (http_only, line) = if line.starts_with("#HttpOnly_") {
+            (true, &line["#HttpOnly_".len()..])
+        } else {
+            (false, line)
+        };
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            continue;
+        }
+
+        let domain = fields[0].to_string();
+        let secure = fields[3] == "TRUE";
+        let expiry: u64 = match fields[4].parse() {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+
+        cookies.push(AddCookieParameters {
+            name: fields[5].to_string(),
+            value: fields[6].to_string(),
+            path: Some(fields[2].to_string()),
+            domain: Some(domain),
+            expiry: if expiry == 0 { None } else { Some(Date::new(expiry)) },
+            secure: secure,
+            httpOnly: http_only,
+            same_site: None,
+        });
+    }
+
+    cookies
+}
+
+/// Serialise cookies back into the Netscape `cookie.txt` format, the
+/// inverse of `from_netscape`. `include_subdomains` is derived from a
+/// leading dot on the domain, and a missing `expiry` is written as `0`
+/// (a session cookie).
+pub fn to_netscape(cookies: &[AddCookieParameters]) -> String {
+    let mut lines = vec!["# Netscape HTTP Cookie File".to_string()];
+
+    for cookie in cookies {
+        let domain = cookie.domain.clone().unwrap_or_default();
+        let include_subdomains = domain.starts_with('.');
+        let path = cookie.path.clone().unwrap_or_else(|| "/".to_string());
+        let expiry = cookie.expiry.as_ref().map(|&Date(x)| x).unwrap_or(0);
+
+        let line = format!("{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                            domain,
+                            if include_subdomains { "TRUE" } else { "FALSE" },
+                            path,
+                            if cookie.secure { "TRUE" } else { "FALSE" },
+                            expiry,
+                            cookie.name,
+                            cookie.value);
+
+        lines.push(if cookie.httpOnly {
+            format!("#HttpOnly_{}", line)
+        } else {
+            line
+        });
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common::Date;
+
+    #[test]
+    fn test_from_netscape_basic() {
+        let input = "# Netscape HTTP Cookie File\n.example.org\tTRUE\t/\tTRUE\t1700000000\tsessionid\tabc123\n";
+        let cookies = from_netscape(input);
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].domain, Some(".example.org".to_string()));
+        assert_eq!(cookies[0].path, Some("/".to_string()));
+        assert!(cookies[0].secure);
+        assert!(!cookies[0].httpOnly);
+        assert_eq!(cookies[0].expiry, Some(Date::new(1700000000)));
+        assert_eq!(cookies[0].name, "sessionid");
+        assert_eq!(cookies[0].value, "abc123");
+    }
+
+    #[test]
+    fn test_from_netscape_http_only_and_session() {
+        let input = "#HttpOnly_example.org\tFALSE\t/login\tFALSE\t0\tauth\ttoken\n";
+        let cookies = from_netscape(input);
+        assert_eq!(cookies.len(), 1);
+        assert!(cookies[0].httpOnly);
+        assert!(!cookies[0].secure);
+        assert_eq!(cookies[0].expiry, None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cookies = vec![
+            AddCookieParameters {
+                name: "a".to_string(),
+                value: "b".to_string(),
+                path: Some("/".to_string()),
+                domain: Some(".example.org".to_string()),
+                expiry: Some(Date::new(123)),
+                secure: true,
+                httpOnly: true,
+                same_site: None,
+            }
+        ];
+        let text = to_netscape(&cookies);
+        let parsed = from_netscape(&text);
+        assert_eq!(parsed, cookies);
+    }
+}
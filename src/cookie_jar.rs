@@ -0,0 +1,142 @@
+use command::AddCookieParameters;
+use common::Date;
+use serde_json;
+use std::collections::HashMap;
+use time;
+
+type CookieKey = (String, String, String);
+
+fn cookie_key(cookie: &AddCookieParameters) -> CookieKey {
+    (cookie.name.clone(),
+     cookie.domain.clone().unwrap_or_default(),
+     cookie.path.clone().unwrap_or_default())
+}
+
+/// A persistent store of cookies that can be checkpointed to and restored
+/// from JSON, so a caller can save a session's login state and replay it
+/// into a fresh session later, the way an HTTP agent persists its cookie
+/// jar across runs.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: HashMap<CookieKey, AddCookieParameters>
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar { cookies: HashMap::new() }
+    }
+
+    /// Insert `cookie`, replacing any existing entry keyed by the same
+    /// `(name, domain, path)`.
+    pub fn insert(&mut self, cookie: AddCookieParameters) {
+        self.cookies.insert(cookie_key(&cookie), cookie);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn save_json(&self) -> serde_json::Result<String> {
+        let cookies: Vec<&AddCookieParameters> = self.cookies.values().collect();
+        serde_json::to_string(&cookies)
+    }
+
+    /// Load a jar from JSON previously produced by `save_json`, evicting
+    /// any cookie whose `expiry` has already passed.
+    pub fn load_json(data: &str) -> serde_json::Result<CookieJar> {
+        let cookies: Vec<AddCookieParameters> = serde_json::from_str(data)?;
+        let now = time::get_time().sec as u64;
+
+        let mut jar = CookieJar::new();
+        for cookie in cookies {
+            let expired = match cookie.expiry {
+                Some(Date(expiry)) => expiry < now,
+                None => false
+            };
+            if !expired {
+                jar.insert(cookie);
+            }
+        }
+        Ok(jar)
+    }
+
+    /// Consume the jar, yielding the cookies as `AddCookieParameters` ready
+    /// to replay into a session via the add-cookie command.
+    pub fn into_add_params(self) -> Vec<AddCookieParameters> {
+        self.cookies.into_iter().map(|(_, cookie)| cookie).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use command::SameSite;
+
+    fn cookie(name: &str, domain: &str, expiry: Option<u64>) -> AddCookieParameters {
+        AddCookieParameters {
+            name: name.to_string(),
+            value: "value".to_string(),
+            path: Some("/".to_string()),
+            domain: Some(domain.to_string()),
+            expiry: expiry.map(Date::new),
+            secure: false,
+            httpOnly: false,
+            same_site: None
+        }
+    }
+
+    #[test]
+    fn test_insert_replaces_existing_key() {
+        let mut jar = CookieJar::new();
+        jar.insert(cookie("session", "example.org", None));
+        jar.insert(cookie("session", "example.org", None));
+        assert_eq!(jar.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_keeps_distinct_keys() {
+        let mut jar = CookieJar::new();
+        jar.insert(cookie("session", "example.org", None));
+        jar.insert(cookie("session", "other.org", None));
+        jar.insert(cookie("other", "example.org", None));
+        assert_eq!(jar.len(), 3);
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let mut jar = CookieJar::new();
+        jar.insert(cookie("session", "example.org", None));
+        let data = jar.save_json().unwrap();
+
+        let loaded = CookieJar::load_json(&data).unwrap();
+        assert_eq!(loaded.len(), 1);
+    }
+
+    #[test]
+    fn test_save_load_round_trip_preserves_same_site() {
+        let mut jar = CookieJar::new();
+        let mut with_same_site = cookie("session", "example.org", None);
+        with_same_site.secure = true;
+        with_same_site.same_site = Some(SameSite::Lax);
+        jar.insert(with_same_site);
+        let data = jar.save_json().unwrap();
+
+        let loaded = CookieJar::load_json(&data).unwrap();
+        let cookies = loaded.into_add_params();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].same_site, Some(SameSite::Lax));
+    }
+
+    #[test]
+    fn test_load_json_evicts_expired_cookies() {
+        let now = time::get_time().sec as u64;
+        let mut jar = CookieJar::new();
+        jar.insert(cookie("expired", "example.org", Some(now - 1)));
+        jar.insert(cookie("current", "example.org", Some(now + 3600)));
+        let data = jar.save_json().unwrap();
+
+        let loaded = CookieJar::load_json(&data).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.into_add_params()[0].name, "current");
+    }
+}
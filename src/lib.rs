@@ -12,6 +12,7 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate time;
+extern crate unicode_segmentation;
 extern crate url;
 
 #[macro_use] pub mod macros;
@@ -20,6 +21,8 @@ pub mod httpapi;
 pub mod capabilities;
 pub mod command;
 pub mod common;
+pub mod cookie_io;
+pub mod cookie_jar;
 pub mod error;
 pub mod server;
 pub mod response;
@@ -1,67 +1,69 @@
+use command::SameSite;
 use common::Date;
 use cookie;
+use serde::{Serialize, Serializer};
 use serde_json::{self, Value};
-use std::convert::From;
 use time;
 
 #[derive(Debug)]
 pub enum WebDriverResponse {
     CloseWindow(CloseWindowResponse),
     Cookie(CookieResponse),
+    Cookies(CookiesResponse),
     DeleteSession,
     ElementRect(ElementRectResponse),
     Generic(ValueResponse),
     NewSession(NewSessionResponse),
+    NewWindow(NewWindowResponse),
     Timeouts(TimeoutsResponse),
     Void,
+    WebAuthnAddVirtualAuthenticator(WebAuthnAddVirtualAuthenticatorResponse),
+    WebAuthnGetCredentials(GetCredentialsResponse),
     WindowRect(WindowRectResponse),
 }
 
+/// Emits `{"value": <payload>}` for whichever payload type a
+/// `WebDriverResponse` variant is carrying, so individual response structs
+/// don't each need to know about the envelope.
+#[derive(Serialize)]
+struct ValueEnvelope<'a, T: Serialize + 'a> {
+    value: &'a T,
+}
+
+impl Serialize for WebDriverResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *self {
+            WebDriverResponse::CloseWindow(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::Cookie(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::Cookies(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::DeleteSession => ValueEnvelope { value: &Value::Null }.serialize(serializer),
+            WebDriverResponse::ElementRect(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::Generic(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::NewSession(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::NewWindow(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::Timeouts(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::Void => ValueEnvelope { value: &Value::Null }.serialize(serializer),
+            WebDriverResponse::WebAuthnAddVirtualAuthenticator(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::WebAuthnGetCredentials(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+            WebDriverResponse::WindowRect(ref x) => ValueEnvelope { value: x }.serialize(serializer),
+        }
+    }
+}
+
 impl WebDriverResponse {
     pub fn to_json_string(self) -> String {
-        let obj = match self {
-            WebDriverResponse::CloseWindow(ref x) => serde_json::to_string(&Value::from(x)),
-            WebDriverResponse::Cookie(ref x) => serde_json::to_string(x),
-            WebDriverResponse::DeleteSession => Ok("{}".to_string()),
-            WebDriverResponse::ElementRect(ref x) => serde_json::to_string(x),
-            WebDriverResponse::Generic(ref x) => serde_json::to_string(x),
-            WebDriverResponse::NewSession(ref x) => serde_json::to_string(x),
-            WebDriverResponse::Timeouts(ref x) => serde_json::to_string(x),
-            WebDriverResponse::Void => Ok("{}".to_string()),
-            WebDriverResponse::WindowRect(ref x) => serde_json::to_string(x),
-        }.unwrap();
-
-        match self {
-            WebDriverResponse::Generic(_) |
-            WebDriverResponse::Cookie(_) => obj,
-            _ => {
-                let mut data = String::with_capacity(11 + obj.len());
-                data.push_str("{\"value\": ");
-                data.push_str(&*obj);
-                data.push_str("}");
-                data
-            }
-        }
+        serde_json::to_string(&self).expect("WebDriverResponse always serializes")
     }
 }
 
 #[derive(Serialize, Debug)]
-pub struct CloseWindowResponse {
-    pub window_handles: Vec<String>,
-}
+pub struct CloseWindowResponse(pub Vec<String>);
 
 impl CloseWindowResponse {
     pub fn new(handles: Vec<String>) -> CloseWindowResponse {
-        CloseWindowResponse { window_handles: handles }
-    }
-}
-
-impl <'a> From<&'a CloseWindowResponse> for Value {
-    fn from(resp: &'a CloseWindowResponse) -> Value {
-        Value::Array(resp.window_handles
-                    .iter()
-                    .map(|x| Value::String(x.clone()))
-                    .collect::<Vec<Value>>())
+        CloseWindowResponse(handles)
     }
 }
 
@@ -80,6 +82,22 @@ impl NewSessionResponse {
     }
 }
 
+#[derive(Serialize, Debug)]
+pub struct NewWindowResponse {
+    pub handle: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+}
+
+impl NewWindowResponse {
+    pub fn new(handle: String, typ: String) -> NewWindowResponse {
+        NewWindowResponse {
+            handle: handle,
+            typ: typ,
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct TimeoutsResponse {
     pub script: u64,
@@ -98,15 +116,11 @@ impl TimeoutsResponse {
 }
 
 #[derive(Serialize, Debug)]
-pub struct ValueResponse {
-    pub value: Value
-}
+pub struct ValueResponse(pub Value);
 
 impl ValueResponse {
     pub fn new(value: Value) -> ValueResponse {
-        ValueResponse {
-            value: value
-        }
+        ValueResponse(value)
     }
 }
 
@@ -146,12 +160,15 @@ pub struct Cookie {
     pub domain: Option<String>,
     pub expiry: Option<Date>,
     pub secure: bool,
-    pub httpOnly: bool
+    pub httpOnly: bool,
+    #[serde(rename = "sameSite", skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<SameSite>
 }
 
 impl Cookie {
     pub fn new(name: String, value: String, path: Option<String>, domain: Option<String>,
-               expiry: Option<Date>, secure: bool, http_only: bool) -> Cookie {
+               expiry: Option<Date>, secure: bool, http_only: bool,
+               same_site: Option<SameSite>) -> Cookie {
         Cookie {
             name: name,
             value: value,
@@ -159,7 +176,8 @@ impl Cookie {
             domain: domain,
             expiry: expiry,
             secure: secure,
-            httpOnly: http_only
+            httpOnly: http_only,
+            same_site: same_site
         }
     }
 }
@@ -183,23 +201,62 @@ impl Into<cookie::Cookie<'static>> for Cookie {
             },
             None => cookie,
         };
+        let cookie = match self.same_site {
+            Some(SameSite::Strict) => cookie.same_site(cookie::SameSite::Strict),
+            Some(SameSite::Lax) => cookie.same_site(cookie::SameSite::Lax),
+            Some(SameSite::None) => cookie.same_site(cookie::SameSite::None),
+            None => cookie,
+        };
         cookie.finish()
     }
 }
 
 #[derive(Serialize, Debug)]
-pub struct CookieResponse {
-    pub value: Vec<Cookie>
-}
+pub struct CookieResponse(pub Cookie);
 
 impl CookieResponse {
-    pub fn new(value: Vec<Cookie>) -> CookieResponse {
-        CookieResponse {
-            value: value
-        }
+    pub fn new(value: Cookie) -> CookieResponse {
+        CookieResponse(value)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CookiesResponse(pub Vec<Cookie>);
+
+impl CookiesResponse {
+    pub fn new(value: Vec<Cookie>) -> CookiesResponse {
+        CookiesResponse(value)
+    }
+}
+
+
+#[derive(Serialize, Debug)]
+pub struct WebAuthnAddVirtualAuthenticatorResponse(pub String);
+
+impl WebAuthnAddVirtualAuthenticatorResponse {
+    pub fn new(id: String) -> WebAuthnAddVirtualAuthenticatorResponse {
+        WebAuthnAddVirtualAuthenticatorResponse(id)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct GetCredentialsResponse(pub Vec<Credential>);
+
+impl GetCredentialsResponse {
+    pub fn new(credentials: Vec<Credential>) -> GetCredentialsResponse {
+        GetCredentialsResponse(credentials)
     }
 }
 
+#[derive(Serialize, Debug)]
+pub struct Credential {
+    pub credentialId: String,
+    pub isResidentCredential: bool,
+    pub rpId: Option<String>,
+    pub privateKey: String,
+    pub userHandle: Option<String>,
+    pub signCount: u64,
+}
 
 #[cfg(test)]
 mod tests {
@@ -207,12 +264,18 @@ mod tests {
     use super::{WebDriverResponse,
                 CloseWindowResponse,
                 CookieResponse,
+                CookiesResponse,
+                Credential,
                 ElementRectResponse,
+                GetCredentialsResponse,
                 NewSessionResponse,
+                NewWindowResponse,
                 ValueResponse,
                 TimeoutsResponse,
+                WebAuthnAddVirtualAuthenticatorResponse,
                 WindowRectResponse,
-                Cookie};
+                Cookie,
+                SameSite};
 
     fn test(resp: WebDriverResponse, expected_str: &str) {
         let data = resp.to_json_string();
@@ -232,6 +295,38 @@ mod tests {
     #[test]
     fn test_cookie() {
         let resp = WebDriverResponse::Cookie(CookieResponse::new(
+            Cookie::new("test".into(),
+                        "test_value".into(),
+                        Some("/".into()),
+                        None,
+                        None,
+                        true,
+                        false,
+                        None)));
+        let expected = r#"{"value": {"name": "test", "value": "test_value", "path": "/",
+"domain": null, "expiry": null, "secure": true, "httpOnly": false}}"#;
+        test(resp, expected);
+    }
+
+    #[test]
+    fn test_cookie_same_site() {
+        let resp = WebDriverResponse::Cookie(CookieResponse::new(
+            Cookie::new("test".into(),
+                        "test_value".into(),
+                        Some("/".into()),
+                        None,
+                        None,
+                        true,
+                        false,
+                        Some(SameSite::Lax))));
+        let expected = r#"{"value": {"name": "test", "value": "test_value", "path": "/",
+"domain": null, "expiry": null, "secure": true, "httpOnly": false, "sameSite": "Lax"}}"#;
+        test(resp, expected);
+    }
+
+    #[test]
+    fn test_cookies() {
+        let resp = WebDriverResponse::Cookies(CookiesResponse::new(
             vec![
                 Cookie::new("test".into(),
                             "test_value".into(),
@@ -239,7 +334,8 @@ mod tests {
                             None,
                             None,
                             true,
-                            false)
+                            false,
+                            None)
             ]));
         let expected = r#"{"value": [{"name": "test", "value": "test_value", "path": "/",
 "domain": null, "expiry": null, "secure": true, "httpOnly": false}]}"#;
@@ -275,6 +371,14 @@ mod tests {
         test(resp, expected);
     }
 
+    #[test]
+    fn test_new_window() {
+        let resp = WebDriverResponse::NewWindow(
+            NewWindowResponse::new("test".into(), "tab".into()));
+        let expected = r#"{"value": {"handle": "test", "type": "tab"}}"#;
+        test(resp, expected);
+    }
+
     #[test]
     fn test_timeouts() {
          let resp = WebDriverResponse::Timeouts(TimeoutsResponse::new(
@@ -283,6 +387,46 @@ mod tests {
         test(resp, expected);
     }
 
+    #[test]
+    fn test_webauthn_add_virtual_authenticator() {
+        let resp = WebDriverResponse::WebAuthnAddVirtualAuthenticator(
+            WebAuthnAddVirtualAuthenticatorResponse::new("authenticator-1".into()));
+        let expected = r#"{"value": "authenticator-1"}"#;
+        test(resp, expected);
+    }
+
+    #[test]
+    fn test_webauthn_get_credentials() {
+        let resp = WebDriverResponse::WebAuthnGetCredentials(
+            GetCredentialsResponse::new(vec![
+                Credential {
+                    credentialId: "Y3JlZA".into(),
+                    isResidentCredential: true,
+                    rpId: Some("example.com".into()),
+                    privateKey: "cHJpdg".into(),
+                    userHandle: None,
+                    signCount: 0,
+                }
+            ]));
+        let expected = r#"{"value": [{"credentialId": "Y3JlZA", "isResidentCredential": true,
+"rpId": "example.com", "privateKey": "cHJpdg", "userHandle": null, "signCount": 0}]}"#;
+        test(resp, expected);
+    }
+
+    #[test]
+    fn test_delete_session() {
+        let resp = WebDriverResponse::DeleteSession;
+        let expected = r#"{"value": null}"#;
+        test(resp, expected);
+    }
+
+    #[test]
+    fn test_void() {
+        let resp = WebDriverResponse::Void;
+        let expected = r#"{"value": null}"#;
+        test(resp, expected);
+    }
+
     #[test]
     fn test_value() {
         let mut value = Map::new();